@@ -7,10 +7,9 @@ extern crate env_logger;
 extern crate image;
 extern crate leechbar;
 
-use leechbar::{Background, Bar, BarBuilder, Color, Component, Foreground, Image, Text};
+use leechbar::{timer, Background, Bar, BarBuilder, Color, Component, Foreground, Image, Text};
 use std::time::Duration;
 use std::ops::Range;
-use std::thread;
 
 // The component state required
 struct ImageComponent {
@@ -68,19 +67,7 @@ impl Component for ImageComponent {
 
     // Update component every three seconds
     fn redraw_timer(&mut self) -> chan::Receiver<()> {
-        // Create a channel for sending out the redraw messages
-        let (tx, rx) = chan::sync(0);
-
-        // Spawn a new thread that loops forever
-        thread::spawn(move || {
-            loop {
-                // Wait 3 seconds, then send an empty message to request a redraw
-                thread::sleep(Duration::from_secs(3));
-                tx.send(());
-            }
-        });
-
-        rx
+        timer::every(Duration::from_secs(3))
     }
 }
 
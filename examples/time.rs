@@ -7,9 +7,8 @@ extern crate env_logger;
 extern crate leechbar;
 extern crate time;
 
-use leechbar::{Bar, BarBuilder, Component, Foreground, Text};
+use leechbar::{timer, Bar, BarBuilder, Component, Foreground, Text};
 use std::time::Duration;
-use std::thread;
 
 // Store important state for the component
 pub struct Time {
@@ -72,22 +71,10 @@ impl Component for Time {
         }
     }
 
-    // Update component every 5 seconds
+    // Update component at the start of every minute
     // It will only redraw when the time has changed and `update` returns `true`
     fn redraw_timer(&mut self) -> chan::Receiver<()> {
-        // Create a channel for sending out the redraw messages
-        let (tx, rx) = chan::sync(0);
-
-        // Spawn a new thread that loops forever
-        thread::spawn(move || {
-            loop {
-                // Wait 5 seconds, then send an empty message to request a redraw
-                thread::sleep(Duration::from_secs(5));
-                tx.send(());
-            }
-        });
-
-        rx
+        timer::aligned(Duration::from_secs(60))
     }
 }
 
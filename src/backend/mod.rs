@@ -0,0 +1,56 @@
+pub mod raster;
+pub mod x11;
+
+use image::{Rgba, RgbaImage};
+use util::geometry::Geometry;
+use util::color::Color;
+use error::*;
+
+// Drawing operations required for rendering the bar
+// Pictures are identified by ids which are created by the backend
+pub trait Backend: Send + Sync {
+    // Create a new fully transparent picture
+    fn create_picture(&self, width: u16, height: u16) -> Result<u32>;
+
+    // Create a picture from premultiplied 32 bit BGRA image data
+    fn create_image(&self, width: u16, height: u16, data: &[u8]) -> Result<u32>;
+
+    // Free a picture, its id must not be used afterwards
+    fn free_picture(&self, picture: u32);
+
+    // Blend a color over an area of a picture
+    fn fill(&self, picture: u32, color: Color, area: Geometry) -> Result<()>;
+
+    // Blend `src` over an area of `target`, starting at `src_x`/`src_y` in the source
+    fn composite(&self, src: u32, src_x: i16, src_y: i16, target: u32, area: Geometry)
+        -> Result<()>;
+
+    // Replace an area of `target` with `src`, including its alpha channel
+    // Used for the window, so translucent pictures don't pile up when they are drawn again
+    fn copy(&self, src: u32, src_x: i16, src_y: i16, target: u32, area: Geometry) -> Result<()>;
+
+    // Picture of the bar window
+    fn window_picture(&self) -> u32;
+
+    // Read back the content of the bar window
+    fn snapshot(&self, width: u16, height: u16) -> Result<RgbaImage>;
+
+    // Submit all pending drawing operations
+    fn flush(&self) {}
+}
+
+// Convert premultiplied 32 bit BGRA data to an RGBA image
+// Without alpha channel every pixel is treated as opaque
+pub(crate) fn bgra_to_image(width: u32, height: u32, data: &[u8], alpha: bool) -> RgbaImage {
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, bgra) in image.pixels_mut().zip(data.chunks(4)) {
+        let alpha = if alpha { bgra[3] } else { 255 };
+        let unpremultiply = |channel: u8| match alpha {
+            0 => 0,
+            _ => (u32::from(channel) * 255 / u32::from(alpha)) as u8,
+        };
+        let (red, green) = (unpremultiply(bgra[2]), unpremultiply(bgra[1]));
+        *pixel = Rgba([red, green, unpremultiply(bgra[0]), alpha]);
+    }
+    image
+}
@@ -0,0 +1,403 @@
+use cairo::{BorrowError, Context, Format, ImageSurface, Operator, Status};
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use util::geometry::Geometry;
+use util::color::Color;
+use backend::{self, Backend};
+use error::*;
+
+/// In-memory rendering target for headless bars.
+///
+/// This allows spawning a bar without an X.Org server, which makes it possible to test the layout
+/// and content of components on machines without a display. Instead of a window, the bar is
+/// drawn to a cairo image surface that can be read using [`image`].
+///
+/// The raster acts as an output of the specified size, the [`BarBuilder`] places the bar on it
+/// like it would on a real output.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use leechbar::{BarBuilder, Raster};
+///
+/// let raster = Raster::new(1920, 1080);
+/// let bar = BarBuilder::new().spawn_headless(&raster).unwrap();
+///
+/// // Get the current content of the bar
+/// let image = raster.image().unwrap();
+/// ```
+///
+/// [`image`]: struct.Raster.html#method.image
+/// [`BarBuilder`]: struct.BarBuilder.html
+#[derive(Clone)]
+pub struct Raster {
+    pub(crate) output: Geometry,
+    state: Arc<Mutex<RasterState>>,
+}
+
+// All surfaces of a raster
+// The ids start at 1, so 0 can never be a valid picture
+struct RasterState {
+    surfaces: HashMap<u32, ImageSurface>,
+    next_id: u32,
+    window: u32,
+}
+
+impl Raster {
+    /// Create a new raster with the size of the output the bar is placed on.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::Raster;
+    ///
+    /// let raster = Raster::new(1920, 1080);
+    /// ```
+    pub fn new(width: u16, height: u16) -> Self {
+        Raster {
+            output: Geometry::new(0, 0, width, height),
+            state: Arc::new(Mutex::new(RasterState {
+                surfaces: HashMap::new(),
+                next_id: 1,
+                window: 0,
+            })),
+        }
+    }
+
+    /// Get the current content of the bar.
+    ///
+    /// This returns an empty image if no bar has been spawned on this raster yet.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::{BarBuilder, Raster};
+    ///
+    /// let raster = Raster::new(1920, 1080);
+    /// let bar = BarBuilder::new().spawn_headless(&raster).unwrap();
+    ///
+    /// let image = raster.image().unwrap();
+    /// ```
+    pub fn image(&self) -> Result<RgbaImage> {
+        let mut state = self.state.lock().unwrap();
+        let window = state.window;
+        let surface = match state.surfaces.get_mut(&window) {
+            Some(surface) => surface,
+            None => return Ok(RgbaImage::new(0, 0)),
+        };
+
+        // Convert from premultiplied BGRA to RGBA
+        let (w, h) = (surface.get_width() as u32, surface.get_height() as u32);
+        let data = surface_data(surface)?;
+        Ok(backend::bgra_to_image(w, h, &data, true))
+    }
+
+    // Create the surface of the bar window
+    pub(crate) fn create_window(&self, width: u16, height: u16) -> Result<()> {
+        let window = self.create_picture(width, height)?;
+
+        // Replace the window of a previous bar
+        let mut state = self.state.lock().unwrap();
+        let old_window = state.window;
+        state.surfaces.remove(&old_window);
+        state.window = window;
+
+        Ok(())
+    }
+
+    // Composite `src` to an area of `target` using a cairo operator
+    fn composite_with(
+        &self,
+        operator: Operator,
+        src: u32,
+        src_x: i16,
+        src_y: i16,
+        target: u32,
+        area: Geometry,
+    ) {
+        let state = self.state.lock().unwrap();
+        let (src, target) = match (state.surfaces.get(&src), state.surfaces.get(&target)) {
+            (Some(src), Some(target)) => (src, target),
+            _ => {
+                warn!("Unable to composite unknown picture {} over {}", src, target);
+                return;
+            }
+        };
+
+        // Align the source with the area on the target
+        let context = Context::new(target);
+        let x = f64::from(area.x) - f64::from(src_x);
+        let y = f64::from(area.y) - f64::from(src_y);
+        context.set_operator(operator);
+        context.set_source_surface(src, x, y);
+        rectangle(&context, area);
+        context.fill();
+    }
+
+    // Store a new surface and return its id
+    fn insert(&self, surface: ImageSurface) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.surfaces.insert(id, surface);
+        id
+    }
+}
+
+impl Backend for Raster {
+    fn create_picture(&self, width: u16, height: u16) -> Result<u32> {
+        let (width, height) = (i32::from(width), i32::from(height));
+        let surface = ImageSurface::create(Format::ARgb32, width, height).map_err(Error::Cairo)?;
+        Ok(self.insert(surface))
+    }
+
+    fn create_image(&self, width: u16, height: u16, data: &[u8]) -> Result<u32> {
+        let (w, h) = (i32::from(width), i32::from(height));
+        let mut surface = ImageSurface::create(Format::ARgb32, w, h).map_err(Error::Cairo)?;
+
+        // Copy the data row by row, the stride of the surface might be bigger than the width
+        if width > 0 {
+            let stride = surface.get_stride() as usize;
+            let row_length = width as usize * 4;
+            let mut surface_data = surface.get_data().map_err(borrow_error)?;
+            for (y, row) in data.chunks(row_length).take(height as usize).enumerate() {
+                surface_data[y * stride..y * stride + row.len()].copy_from_slice(row);
+            }
+        }
+
+        Ok(self.insert(surface))
+    }
+
+    fn free_picture(&self, picture: u32) {
+        self.state.lock().unwrap().surfaces.remove(&picture);
+    }
+
+    fn fill(&self, picture: u32, color: Color, area: Geometry) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        let surface = match state.surfaces.get(&picture) {
+            Some(surface) => surface,
+            None => {
+                warn!("Unable to fill unknown picture {}", picture);
+                return Ok(());
+            }
+        };
+
+        let context = Context::new(surface);
+        let (red, green, blue, alpha) = color.as_fractions();
+        context.set_source_rgba(red, green, blue, alpha);
+        rectangle(&context, area);
+        context.fill();
+
+        Ok(())
+    }
+
+    fn composite(
+        &self,
+        src: u32,
+        src_x: i16,
+        src_y: i16,
+        target: u32,
+        area: Geometry,
+    ) -> Result<()> {
+        self.composite_with(Operator::Over, src, src_x, src_y, target, area);
+        Ok(())
+    }
+
+    fn copy(&self, src: u32, src_x: i16, src_y: i16, target: u32, area: Geometry) -> Result<()> {
+        self.composite_with(Operator::Source, src, src_x, src_y, target, area);
+        Ok(())
+    }
+
+    fn window_picture(&self) -> u32 {
+        self.state.lock().unwrap().window
+    }
+
+    fn snapshot(&self, _width: u16, _height: u16) -> Result<RgbaImage> {
+        self.image()
+    }
+}
+
+// Add the path of a geometry to a cairo context
+fn rectangle(context: &Context, area: Geometry) {
+    let (x, y) = (f64::from(area.x), f64::from(area.y));
+    context.rectangle(x, y, f64::from(area.width), f64::from(area.height));
+}
+
+// Get the premultiplied BGRA data of a surface without padding between the rows
+pub(crate) fn surface_data(surface: &mut ImageSurface) -> Result<Vec<u8>> {
+    let stride = surface.get_stride() as usize;
+    let row_length = surface.get_width() as usize * 4;
+    let height = surface.get_height() as usize;
+    if row_length == 0 {
+        return Ok(Vec::new());
+    }
+
+    let data = surface.get_data().map_err(borrow_error)?;
+    let mut packed = Vec::with_capacity(row_length * height);
+    for row in data.chunks(stride) {
+        packed.extend_from_slice(&row[..row_length]);
+    }
+
+    Ok(packed)
+}
+
+// Surfaces are never shared while accessing their data, so borrowing only fails because of cairo
+fn borrow_error(err: BorrowError) -> Error {
+    match err {
+        BorrowError::Cairo(status) => Error::Cairo(status),
+        BorrowError::NonExclusive => Error::Cairo(Status::SurfaceFinished),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgba};
+    use std::any::Any;
+    use chan::{self, Sender};
+    use {Alignment, Background, Bar, BarBuilder, Component, ComponentHandle, Foreground, Image,
+         Text, Width};
+
+    struct TestComponent {
+        alignment: Alignment,
+        background: Background,
+        foreground: Foreground,
+        width: Width,
+    }
+
+    impl Component for TestComponent {
+        fn alignment(&self) -> Alignment {
+            self.alignment
+        }
+
+        fn background(&self) -> Background {
+            self.background.clone()
+        }
+
+        fn foreground(&self) -> Foreground {
+            self.foreground.clone()
+        }
+
+        fn width(&self) -> Width {
+            self.width
+        }
+
+        // Reply to synchronize with the scheduler
+        fn message(&mut self, message: Box<Any + Send>) -> bool {
+            if let Ok(reply) = message.downcast::<Sender<()>>() {
+                reply.send(());
+            }
+            false
+        }
+    }
+
+    // 200x20 bar with blue background on a 200x100 raster
+    fn bar(raster: &Raster) -> Bar {
+        BarBuilder::new()
+            .height(20)
+            .background_color(Color::new(0, 0, 255, 255))
+            .spawn_headless(raster)
+            .unwrap()
+    }
+
+    fn component(alignment: Alignment, width: u16) -> TestComponent {
+        TestComponent {
+            alignment,
+            background: Background::new(),
+            foreground: Foreground::new(),
+            width: Width::new().fixed(width),
+        }
+    }
+
+    // Wait until the scheduler has processed everything sent before
+    fn sync(handle: &ComponentHandle) {
+        let (tx, rx) = chan::sync::<()>(1);
+        handle.send(tx);
+        rx.recv();
+    }
+
+    #[test]
+    fn surface_has_bar_size() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let handle = bar.add(component(Alignment::LEFT, 10));
+        sync(&handle);
+
+        let image = raster.image().unwrap();
+        assert_eq!(image.dimensions(), (200, 20));
+    }
+
+    #[test]
+    fn background_color_component() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let mut comp = component(Alignment::RIGHT, 30);
+        comp.background = Color::new(0, 255, 0, 255).into();
+        let handle = bar.add(comp);
+        sync(&handle);
+
+        let image = raster.image().unwrap();
+        assert_eq!(*image.get_pixel(170, 0), Rgba([0, 255, 0, 255]));
+        assert_eq!(*image.get_pixel(199, 19), Rgba([0, 255, 0, 255]));
+        assert_eq!(*image.get_pixel(169, 10), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn translucent_component_redrawn_twice() {
+        let raster = Raster::new(200, 100);
+        let mut bar = BarBuilder::new()
+            .height(20)
+            .background_color(Color::new(0, 0, 0, 0))
+            .spawn_headless(&raster)
+            .unwrap();
+        let mut comp = component(Alignment::LEFT, 10);
+        comp.background = Color::new(255, 0, 0, 128).into();
+        let handle = bar.add(comp);
+        sync(&handle);
+        let first = *raster.image().unwrap().get_pixel(5, 5);
+
+        // The window is replaced instead of blending the component over itself
+        handle.request_redraw();
+        sync(&handle);
+        let image = raster.image().unwrap();
+        assert_eq!(first[3], 128);
+        assert_eq!(*image.get_pixel(5, 5), first);
+    }
+
+    #[test]
+    fn image_component() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let red = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, Rgba([255, 0, 0, 255])));
+        let mut comp = component(Alignment::LEFT, 10);
+        comp.background = Image::new(&bar, &red).unwrap().into();
+        let handle = bar.add(comp);
+        sync(&handle);
+
+        // The image is drawn at the top of the component
+        let image = raster.image().unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(9, 9), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(5, 10), Rgba([0, 0, 255, 255]));
+        assert_eq!(*image.get_pixel(10, 5), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn text_component() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let white = Color::new(255, 255, 255, 255);
+        let text = Text::new(&bar, "Hello", None, Some(white)).unwrap();
+        let mut comp = component(Alignment::LEFT, 100);
+        comp.foreground = text.into();
+        let handle = bar.add(comp);
+        sync(&handle);
+
+        // The text is drawn inside the component and nowhere else
+        let image = raster.image().unwrap();
+        let is_text = |x: u32, y: u32| image.get_pixel(x, y)[0] > 128;
+        assert!((0..100).any(|x| (0..20).any(|y| is_text(x, y))));
+        assert!(!(100..200).any(|x| (0..20).any(|y| is_text(x, y))));
+    }
+}
@@ -0,0 +1,151 @@
+use backend::{self, Backend};
+use image::RgbaImage;
+use util::geometry::Geometry;
+use util::color::Color;
+use xcb::{self, Rectangle};
+use std::sync::Arc;
+use error::*;
+
+// Backend drawing to the bar window using the XRender extension
+pub struct X11 {
+    pub conn: Arc<xcb::Connection>,
+    pub root: u32,
+    pub window: u32,
+    pub window_pict: u32,
+    pub gcontext: u32,
+    // Only created for transparent windows, which don't use the root visual
+    pub colormap: Option<u32>,
+    pub format24: u32,
+    pub format32: u32,
+}
+
+impl X11 {
+    // Composite `src` to an area of `target` using a render operator
+    fn composite_with(
+        &self,
+        op: u32,
+        src: u32,
+        src_x: i16,
+        src_y: i16,
+        target: u32,
+        area: Geometry,
+    ) -> Result<()> {
+        let (x, y, w, h) = (area.x, area.y, area.width, area.height);
+        let (op, conn) = (op as u8, &self.conn);
+        xtry!(@render composite_checked, conn, op, src, 0, target, src_x, src_y, 0, 0, x, y, w, h);
+        Ok(())
+    }
+
+    // Free the window and all resources used for drawing to it
+    pub fn destroy(&self, window_destroyed: bool) {
+        xcb::render::free_picture(&self.conn, self.window_pict);
+        xcb::free_gc(&self.conn, self.gcontext);
+        if !window_destroyed {
+            xcb::destroy_window(&self.conn, self.window);
+        }
+        if let Some(colormap) = self.colormap {
+            xcb::free_colormap(&self.conn, colormap);
+        }
+    }
+}
+
+impl Backend for X11 {
+    fn create_picture(&self, width: u16, height: u16) -> Result<u32> {
+        let conn = &self.conn;
+
+        // Create pixmap with empty background
+        let pix = conn.generate_id();
+        xtry!(create_pixmap_checked, conn, 32, pix, self.window, width, height);
+        let rect = &[Rectangle::new(0, 0, width, height)];
+        let res = xcb::poly_fill_rectangle_checked(conn, pix, self.gcontext, rect).request_check();
+
+        // Create picture from pixmap
+        let picture = conn.generate_id();
+        let res = res.and_then(|_| {
+            xcb::render::create_picture_checked(conn, picture, pix, self.format32, &[])
+                .request_check()
+        });
+
+        // The picture keeps a reference to the pixmap, so it can be freed right away
+        xcb::free_pixmap(conn, pix);
+        res.map_err(|e| XError::new("create_picture", &e))?;
+
+        Ok(picture)
+    }
+
+    fn create_image(&self, width: u16, height: u16, data: &[u8]) -> Result<u32> {
+        let conn = &self.conn;
+        let (w, h) = (width, height);
+
+        // Copy image data to a pixmap
+        let pix = conn.generate_id();
+        xtry!(create_pixmap_checked, conn, 32, pix, self.window, w, h);
+        let res = xcb::put_image_checked(conn, 2u8, pix, self.gcontext, w, h, 0, 0, 0, 32, data)
+            .request_check()
+            .map_err(|e| XError::new("put_image", &e));
+
+        // Create picture from pixmap
+        let picture = conn.generate_id();
+        let res = res.and_then(|_| {
+            xcb::render::create_picture_checked(conn, picture, pix, self.format32, &[])
+                .request_check()
+                .map_err(|e| XError::new("create_picture", &e))
+        });
+
+        // Free the unneeded pixmap
+        xcb::free_pixmap(conn, pix);
+        res?;
+
+        Ok(picture)
+    }
+
+    fn free_picture(&self, picture: u32) {
+        xcb::render::free_picture(&self.conn, picture);
+    }
+
+    fn fill(&self, picture: u32, color: Color, area: Geometry) -> Result<()> {
+        let op = xcb::render::PICT_OP_OVER as u8;
+        let rect = [Rectangle::new(area.x, area.y, area.width, area.height)];
+        let color = color.as_render_color();
+        xtry!(@render fill_rectangles_checked, &self.conn, op, picture, color, &rect);
+        Ok(())
+    }
+
+    fn composite(
+        &self,
+        src: u32,
+        src_x: i16,
+        src_y: i16,
+        target: u32,
+        area: Geometry,
+    ) -> Result<()> {
+        let op = xcb::render::PICT_OP_OVER;
+        self.composite_with(op, src, src_x, src_y, target, area)
+    }
+
+    fn copy(&self, src: u32, src_x: i16, src_y: i16, target: u32, area: Geometry) -> Result<()> {
+        let op = xcb::render::PICT_OP_SRC;
+        self.composite_with(op, src, src_x, src_y, target, area)
+    }
+
+    fn window_picture(&self) -> u32 {
+        self.window_pict
+    }
+
+    fn snapshot(&self, width: u16, height: u16) -> Result<RgbaImage> {
+        // Both 24 and 32 bit windows use 32 bits per pixel in the ZPixmap format
+        let format = xcb::IMAGE_FORMAT_Z_PIXMAP as u8;
+        let reply = xcb::get_image(&self.conn, format, self.window, 0, 0, width, height, !0)
+            .get_reply()
+            .map_err(|e| XError::new("get_image", &e))?;
+
+        // The alpha byte is undefined for windows without alpha channel
+        let alpha = reply.depth() == 32;
+        let (w, h) = (u32::from(width), u32::from(height));
+        Ok(backend::bgra_to_image(w, h, reply.data(), alpha))
+    }
+
+    fn flush(&self) {
+        self.conn.flush();
+    }
+}
@@ -1,18 +1,54 @@
 use component::bar_component::BarComponent;
-use image::{DynamicImage, GenericImage};
-use xcb::{self, randr, Rectangle};
+use backend::raster::Raster;
+use backend::x11::X11;
+use backend::Backend;
+use image::{ColorType, DynamicImage, GenericImage, RgbaImage};
+use image::png::PNGEncoder;
+use xcb::{self, randr};
+use component::handle::ComponentHandle;
+use component::info::ComponentInfo;
 use component::{img, Component};
+#[cfg(feature = "futures")]
+use component::async_component::AsyncComponent;
+use util::background_mode::BackgroundMode;
 use util::geometry::Geometry;
-use std::sync::{Arc, Mutex};
-use builder::BarBuilder;
+use util::position::Position;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use builder::{BarBuilder, BarWidth};
 use util::color::Color;
 use event::Event;
+use std::path::Path;
+use std::fs::File;
 use std::thread;
+use std::cmp;
 use error::*;
+use scheduler::{self, Task};
+use chan::{self, Sender};
 use render;
-use chan;
 use util;
 
+// Root window properties used by wallpaper setters to store the wallpaper pixmap
+const WALLPAPER_ATOMS: [&str; 2] = ["_XROOTPMAP_ID", "ESETROOT_PMAP_ID"];
+
+// Stops the scheduler once every bar outside of the scheduler has been dropped
+// The scheduler and component handles don't hold it, otherwise it would never be dropped
+pub(crate) struct StopGuard {
+    tasks: Sender<Task>,
+    quit: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        let mut quit = self.quit.0.lock().unwrap();
+        if !*quit {
+            debug!("Bar dropped, stopping component scheduler…");
+            *quit = true;
+            self.tasks.send(Task::Stop);
+        }
+    }
+}
+
 /// The main bar.
 ///
 /// # Examples
@@ -26,18 +62,28 @@ use util;
 ///
 /// let bar = BarBuilder::new().spawn().unwrap();
 /// ```
+///
+/// After the bar and all of its clones have been dropped, the thread running the components is
+/// stopped. Handles of components do not keep it running.
 #[derive(Clone)]
 pub struct Bar {
-    pub(crate) conn: Arc<xcb::Connection>,
-    pub(crate) geometry: Geometry,
-    pub(crate) window: u32,
-    pub(crate) window_pict: u32,
-    pub(crate) gcontext: u32,
-    pub(crate) background: u32,
+    pub(crate) backend: Arc<Backend>,
+    pub(crate) x11: Option<Arc<X11>>,
+    pub(crate) geometry: Arc<Mutex<Geometry>>,
+    pub(crate) position: Position,
+    pub(crate) output: Option<String>,
+    pub(crate) height: u16,
+    pub(crate) width: Option<BarWidth>,
+    pub(crate) margins: (u16, u16),
+    pub(crate) background: Arc<Mutex<BarBackground>>,
     pub(crate) font: Option<String>,
     pub(crate) components: Arc<Mutex<Vec<BarComponent>>>,
-    pub(crate) format32: u32,
-    pub(crate) format24: u32,
+    pub(crate) hidden: Arc<AtomicBool>,
+    pub(crate) quit: Arc<(Mutex<bool>, Condvar)>,
+    pub(crate) tasks: Sender<Task>,
+    pub(crate) scheduler: Arc<Mutex<Option<thread::JoinHandle<()>>>>,
+    // Only held to stop the scheduler on drop, `None` for the scheduler and component handles
+    pub(crate) _guard: Option<Arc<StopGuard>>,
     pub(crate) color: Color,
     pub(crate) component_ids: [u32; 3],
     pub(crate) text_yoffset: i16,
@@ -45,75 +91,147 @@ pub struct Bar {
 
 impl Bar {
     // Create a new bar
-    pub(crate) fn new(builder: BarBuilder) -> ::std::result::Result<Self, BarError> {
+    pub(crate) fn new(builder: BarBuilder) -> Result<Self> {
         // Connect to the X server
-        let conn = xcb::Connection::connect(None).map_err(|_| BarErrorKind::ConnectionRefused)?;
+        let conn = xcb::Connection::connect(None).map_err(Error::ConnectionRefused)?;
         let conn = Arc::new(conn.0);
+        query_randr(&conn)?;
 
         // Get geometry of the specified display
-        let info = screen_info(&conn, builder.output)?;
-        let geometry = Geometry::new(info.x(), info.y(), info.width(), builder.height);
+        let info = screen_info(&conn, builder.output.clone())?;
+        let output = Geometry::new(info.x(), info.y(), info.width(), info.height());
+        let (position, height) = (builder.position, builder.height);
+        let geometry = bar_geometry(output, position, height, builder.width, builder.margins);
+
+        // Get the 32 bit visual if transparency is enabled
+        let argb_visual = if builder.transparent {
+            let screen = util::screen(&conn)?;
+            let visual = util::find_visualtype32(&screen).ok_or(Error::NoArgbVisual)?;
+            Some(visual.visual_id())
+        } else {
+            None
+        };
+
+        // Get 24 bit and 32 bit image formats
+        let (format24, format32) = image_formats(&conn)?;
+        let root = util::screen(&conn)?.root();
 
         // Create the window
         let name = builder.name.as_bytes();
-        let window = create_window(&conn, geometry, builder.background_color, name)?;
-
-        // Get 24 bit and 32 bit image formats
-        let (format24, format32) = image_formats(&conn);
-
-        // Create a GC with 32 bit depth
-        let gcontext = {
-            // First create a dummy pixmap with 32 bit depth
-            let pix32 = conn.generate_id();
-            xcb::create_pixmap_checked(&conn, 32, pix32, window, 1, 1)
-                .request_check()
-                .expect("Unable to create GC dummy pixmap");
-
-            // Then create a gc from that pixmap
-            let gc = conn.generate_id();
-            xcb::create_gc_checked(&conn, gc, pix32, &[])
-                .request_check()
-                .expect("Unable to create GC");
-
-            // Free pixmap after creating the gc
-            xcb::free_pixmap_checked(&conn, pix32)
-                .request_check()
-                .expect("Unable to free GC dummy pixmap");
-
-            gc
+        let bg_col = builder.background_color;
+        let (window, colormap) =
+            create_window(&conn, geometry, position, bg_col, name, argb_visual)?;
+
+        // Transparent windows require a picture with alpha channel
+        let window_format = if argb_visual.is_some() { format32 } else { format24 };
+        let (gcontext, window_pict) = match create_graphics(&conn, window, window_format) {
+            Ok(graphics) => graphics,
+            Err(e) => {
+                destroy_window(&conn, window, colormap);
+                return Err(e);
+            }
         };
 
-        // Create picture for the window
-        let window_pict = conn.generate_id();
-        xcb::render::create_picture_checked(&conn, window_pict, window, format24, &[])
-            .request_check()
-            .expect("Unable to create window picture");
-
-        // Create background picture
-        let (bg_col, bg_img) = (builder.background_color, builder.background_image);
-        let background =
-            create_background_picture(&conn, window, gcontext, format32, geometry, bg_col, bg_img);
-
-        // Create an empty skeleton bar
-        Ok(Bar {
+        let x11 = Arc::new(X11 {
             conn,
+            root,
             window,
-            geometry,
+            window_pict,
             gcontext,
+            colormap,
             format24,
             format32,
-            background,
-            window_pict,
+        });
+
+        // Don't leave the window behind if the bar can't be created
+        let bar = Bar::with_backend(builder, x11.clone(), Some(x11.clone()), geometry);
+        if bar.is_err() {
+            x11.destroy(false);
+        }
+        bar
+    }
+
+    // Create a new bar which renders to a raster instead of a window
+    pub(crate) fn headless(builder: BarBuilder, raster: &Raster) -> Result<Self> {
+        let (position, height) = (builder.position, builder.height);
+        let (width, margins) = (builder.width, builder.margins);
+        let geometry = bar_geometry(raster.output, position, height, width, margins);
+        raster.create_window(geometry.width, geometry.height)?;
+        let bar = Bar::with_backend(builder, Arc::new(raster.clone()), None, geometry)?;
+
+        // Without a window there are no expose events, so the background is drawn right away
+        render::relayout(&bar)?;
+        Ok(bar)
+    }
+
+    // Create the bar state shared by all backends
+    fn with_backend(
+        builder: BarBuilder,
+        backend: Arc<Backend>,
+        x11: Option<Arc<X11>>,
+        geometry: Geometry,
+    ) -> Result<Self> {
+        // Create background picture
+        let mut background = BarBackground {
+            picture: 0,
+            color: builder.background_color,
+            image: builder.background_image,
+            mode: builder.background_mode,
+            pseudo_transparent: builder.pseudo_transparent,
+        };
+        let x11_ref = x11.as_ref().map(|x11| &**x11);
+        background.picture = create_background_picture(&*backend, x11_ref, geometry, &background)?;
+
+        // Create an empty skeleton bar
+        let (tasks, tasks_rx) = chan::async();
+        let bar = Bar {
+            backend,
+            x11,
+            position: builder.position,
+            output: builder.output,
+            height: builder.height,
+            width: builder.width,
+            margins: builder.margins,
+            geometry: Arc::new(Mutex::new(geometry)),
+            background: Arc::new(Mutex::new(background)),
             font: builder.font,
             component_ids: [0, 1, 2],
             color: builder.foreground_color,
             text_yoffset: builder.text_yoffset,
             components: Arc::new(Mutex::new(Vec::new())),
-        })
+            hidden: Arc::new(AtomicBool::new(false)),
+            quit: Arc::new((Mutex::new(false), Condvar::new())),
+            _guard: None,
+            tasks,
+            scheduler: Arc::new(Mutex::new(None)),
+        };
+        let bar = Bar {
+            _guard: Some(Arc::new(StopGuard {
+                tasks: bar.tasks.clone(),
+                quit: Arc::clone(&bar.quit),
+            })),
+            ..bar
+        };
+
+        // Start the thread running all components
+        let scheduler = scheduler::spawn(bar.unguarded(), tasks_rx);
+        *bar.scheduler.lock().unwrap() = Some(scheduler);
+
+        Ok(bar)
     }
 
     /// Start the event loop of the bar. This handles all X.Org events and is blocking.
     ///
+    /// When the output configuration changes, for example after connecting a monitor or changing
+    /// its resolution, the bar is moved and resized to fit its output again.
+    ///
+    /// The event loop returns after [`quit`] has been called or the window has been destroyed.
+    /// Before returning, the thread running the components is stopped and all X.Org resources of
+    /// the bar are freed.
+    ///
+    /// Headless bars do not receive any events, for them this only blocks until [`quit`] has been
+    /// called.
+    ///
     /// It **must** be called after adding all your components.
     ///
     /// # Examples
@@ -124,76 +242,413 @@ impl Bar {
     /// let bar = BarBuilder::new().spawn().unwrap();
     /// bar.start_event_loop();
     /// ```
+    ///
+    /// [`quit`]: struct.Bar.html#method.quit
     pub fn start_event_loop(&self) {
         info!("Started event loop");
-        loop {
-            if let Some(event) = self.conn.wait_for_event() {
-                let r = event.response_type();
-                if r == xcb::EXPOSE {
-                    debug!("Received expose event, redrawing…");
-
-                    // Composite bg over self again if the image exists
-                    let (w, h) = (self.geometry.width, self.geometry.height);
-                    let res = self.composite_picture(self.background, 0, 0, w, h);
-                    err!(res, "Unable to composite background");
-
-                    // Redraw components
-                    let components = self.components.lock().unwrap();
-                    for component in &*components {
-                        let geometry = component.geometry;
-                        if geometry.width > 0 && geometry.height > 0 {
-                            let res = component.redraw(self);
-                            err!(res, "Unable to redraw component");
-                        }
+        let window_destroyed = match self.x11 {
+            Some(ref x11) => self.handle_events(x11),
+            None => {
+                self.wait_for_quit();
+                false
+            }
+        };
+
+        self.teardown(window_destroyed);
+        info!("Stopped event loop");
+    }
+
+    // Handle X.Org events until the bar is stopped
+    // Returns `true` if the loop stopped because the window has been destroyed
+    fn handle_events(&self, x11: &X11) -> bool {
+        let conn = &x11.conn;
+
+        // Listen for changes of the output configuration
+        let root = x11.root;
+        let mask = randr::NOTIFY_MASK_SCREEN_CHANGE | randr::NOTIFY_MASK_CRTC_CHANGE;
+        randr::select_input(conn, root, mask as u16);
+        let randr_base = conn.get_extension_data(randr::id()).map(|data| data.first_event());
+
+        // Listen for wallpaper changes when it is used as background
+        let pseudo_transparent = self.background.lock().unwrap().pseudo_transparent;
+        let wallpaper_atoms = if pseudo_transparent {
+            let values = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE)];
+            xcb::change_window_attributes(conn, root, &values);
+            WALLPAPER_ATOMS
+                .iter()
+                .filter_map(|name| xcb::intern_atom(conn, false, name).get_reply().ok())
+                .map(|reply| reply.atom())
+                .collect::<Vec<u32>>()
+        } else {
+            Vec::new()
+        };
+        conn.flush();
+
+        while let Some(event) = conn.wait_for_event() {
+            // Ignore the flag for events sent by `send_event`
+            let r = event.response_type() & !0x80;
+            if r == xcb::EXPOSE {
+                debug!("Received expose event, redrawing…");
+
+                // Composite bg over self again if the image exists
+                let geometry = self.geometry();
+                let geometry = Geometry::new(0, 0, geometry.width, geometry.height);
+                let res = self.composite_picture(self.background(), 0, 0, geometry);
+                err!(res, "Unable to composite background");
+
+                // Redraw components
+                let components = self.components.lock().unwrap();
+                for component in &*components {
+                    let geometry = component.geometry;
+                    if geometry.width > 0 && geometry.height > 0 {
+                        let res = component.redraw(self);
+                        err!(res, "Unable to redraw component");
                     }
-                } else if r == xcb::MOTION_NOTIFY {
-                    let event: &xcb::MotionNotifyEvent = unsafe { xcb::cast_event(&event) };
-                    debug!("Mouse moved to {}-{}", event.event_x(), event.event_y());
-                    self.propagate_event(event.into());
-                } else if r == xcb::BUTTON_PRESS || r == xcb::BUTTON_RELEASE {
-                    let event: &xcb::ButtonPressEvent = unsafe { xcb::cast_event(&event) };
-                    debug!(
-                        "Mouse button {} pressed at {}",
-                        event.detail(),
-                        event.event_x()
-                    );
-                    self.propagate_event(event.into());
+                }
+                self.backend.flush();
+            } else if r == xcb::MOTION_NOTIFY {
+                let event: &xcb::MotionNotifyEvent = unsafe { xcb::cast_event(&event) };
+                debug!("Mouse moved to {}-{}", event.event_x(), event.event_y());
+                self.propagate_event(event.into());
+            } else if r == xcb::BUTTON_PRESS || r == xcb::BUTTON_RELEASE {
+                let event: &xcb::ButtonPressEvent = unsafe { xcb::cast_event(&event) };
+                debug!(
+                    "Mouse button {} pressed at {}",
+                    event.detail(),
+                    event.event_x()
+                );
+                self.propagate_event(event.into());
+            } else if r == xcb::PROPERTY_NOTIFY {
+                let event: &xcb::PropertyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                if event.window() == root && wallpaper_atoms.contains(&event.atom()) {
+                    debug!("Received wallpaper change event, redrawing…");
+                    let res = self.redraw_background();
+                    err!(res, "Unable to update wallpaper");
+                }
+            } else if randr_base.map_or(false, |base| {
+                r == base + randr::SCREEN_CHANGE_NOTIFY || r == base + randr::NOTIFY
+            }) {
+                debug!("Received output change event, updating geometry…");
+                let res = self.update_geometry(x11);
+                err!(res, "Unable to update bar geometry");
+            } else if r == xcb::CLIENT_MESSAGE && self.is_quitting() {
+                debug!("Received quit request, stopping event loop…");
+                return false;
+            } else if r == xcb::DESTROY_NOTIFY {
+                let event: &xcb::DestroyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                if event.window() == x11.window {
+                    debug!("Bar window has been destroyed, stopping event loop…");
+                    return true;
                 }
             }
         }
+
+        false
     }
 
-    // Propagate event to the component
-    fn propagate_event(&self, mut event: Event) {
-        let x = match event {
-            Event::ClickEvent(ref e) => e.position.x,
-            Event::MotionEvent(ref e) => e.position.x,
+    // Block until the bar is stopped, headless bars don't receive any events
+    fn wait_for_quit(&self) {
+        let (ref lock, ref cvar) = *self.quit;
+        let mut quit = lock.lock().unwrap();
+        while !*quit {
+            quit = cvar.wait(quit).unwrap();
+        }
+    }
+
+    /// Stop the bar.
+    ///
+    /// This makes [`start_event_loop`] return, it can be called from any thread. The thread
+    /// running the components is stopped and joined and the window and its resources are freed
+    /// before the event loop returns. The bar can not be used anymore afterwards, a new bar can
+    /// be spawned with the [`BarBuilder`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::BarBuilder;
+    /// use std::thread;
+    ///
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    ///
+    /// let handle = bar.clone();
+    /// thread::spawn(move || handle.quit());
+    ///
+    /// bar.start_event_loop();
+    /// ```
+    ///
+    /// [`start_event_loop`]: struct.Bar.html#method.start_event_loop
+    /// [`BarBuilder`]: struct.BarBuilder.html
+    pub fn quit(&self) {
+        {
+            let (ref lock, ref cvar) = *self.quit;
+            let mut quit = lock.lock().unwrap();
+            if *quit {
+                return;
+            }
+            *quit = true;
+            cvar.notify_all();
+        }
+
+        // Wake up the event loop with an empty client message
+        if let Some(ref x11) = self.x11 {
+            let data = xcb::ClientMessageData::from_data32([0; 5]);
+            let event = xcb::ClientMessageEvent::new(32, x11.window, xcb::ATOM_NONE, data);
+            let mask = xcb::EVENT_MASK_NO_EVENT;
+            xcb::send_event(&x11.conn, false, x11.window, mask, &event);
+            x11.conn.flush();
+        }
+    }
+
+    // Clone of the bar which does not keep the scheduler running
+    pub(crate) fn unguarded(&self) -> Bar {
+        Bar {
+            _guard: None,
+            ..self.clone()
+        }
+    }
+
+    // Check if the bar is being stopped
+    pub(crate) fn is_quitting(&self) -> bool {
+        *self.quit.0.lock().unwrap()
+    }
+
+    // Stop the component scheduler and free every resource owned by the bar
+    fn teardown(&self, window_destroyed: bool) {
+        *self.quit.0.lock().unwrap() = true;
+
+        // Wait until the scheduler has finished its current task
+        self.tasks.send(Task::Stop);
+        let scheduler = self.scheduler.lock().unwrap().take();
+        if let Some(scheduler) = scheduler {
+            if scheduler.join().is_err() {
+                warn!("Component scheduler panicked during shutdown");
+            }
+        }
+
+        // Free component pictures
+        let mut components = self.components.lock().unwrap();
+        for component in components.drain(..) {
+            if component.picture != 0 {
+                self.backend.free_picture(component.picture);
+            }
+        }
+        self.backend.free_picture(self.background());
+
+        // Free the window and its resources
+        if let Some(ref x11) = self.x11 {
+            x11.destroy(window_destroyed);
+        }
+        self.backend.flush();
+    }
+
+    // Move and resize the bar after the output configuration has changed
+    fn update_geometry(&self, x11: &X11) -> Result<()> {
+        // Get geometry of the output, fall back to the primary output if it's gone
+        let conn = &x11.conn;
+        let info = screen_info(conn, self.output.clone()).or_else(|_| screen_info(conn, None));
+        let info = match info {
+            Ok(info) => info,
+            Err(e) => {
+                warn!("Unable to find output for bar: {}", e);
+                return Ok(());
+            }
         };
+        let output = Geometry::new(info.x(), info.y(), info.width(), info.height());
+        let (position, height) = (self.position, self.height);
+        let geometry = bar_geometry(output, position, height, self.width, self.margins);
+
+        // Nothing to do if the bar has not been moved
+        if geometry == self.geometry() {
+            return Ok(());
+        }
+        info!("Output changed, moving bar to {:?}", geometry);
+
+        // Move and resize the window
+        let values = [
+            (xcb::CONFIG_WINDOW_X as u16, geometry.x as u32),
+            (xcb::CONFIG_WINDOW_Y as u16, geometry.y as u32),
+            (xcb::CONFIG_WINDOW_WIDTH as u16, u32::from(geometry.width)),
+            (xcb::CONFIG_WINDOW_HEIGHT as u16, u32::from(geometry.height)),
+        ];
+        xtry!(configure_window_checked, conn, x11.window, &values);
+        if !self.is_hidden() {
+            set_struts(conn, x11.window, geometry, self.position)?;
+        }
+
+        // Recreate the background with the new size and move all components
+        self.replace_background(geometry)
+    }
+
+    /// Change the background color of the bar.
+    ///
+    /// This replaces the color set by [`BarBuilder::background_color`] and redraws the whole bar.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::{BarBuilder, Color};
+    ///
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    /// bar.set_background_color(Color::new(255, 255, 255, 255)).unwrap();
+    /// ```
+    ///
+    /// [`BarBuilder::background_color`]: struct.BarBuilder.html#method.background_color
+    pub fn set_background_color(&self, color: Color) -> Result<()> {
+        self.background.lock().unwrap().color = color;
+
+        // Update the color used by X.Org when clearing the window
+        if let Some(ref x11) = self.x11 {
+            // Only transparent windows have their own colormap
+            let pixel = if x11.colormap.is_some() {
+                color.as_premultiplied_pixel()
+            } else {
+                color.into()
+            };
+            let values = [(xcb::CW_BACK_PIXEL, pixel)];
+            xtry!(change_window_attributes_checked, &x11.conn, x11.window, &values);
+        }
+
+        self.redraw_background()
+    }
+
+    /// Change the background image of the bar.
+    ///
+    /// This replaces the image set by [`BarBuilder::background_image`] and redraws the whole bar.
+    /// Passing `None` removes the background image.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # extern crate leechbar;
+    /// extern crate image;
+    /// use leechbar::BarBuilder;
+    ///
+    /// # fn main() {
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    /// let image = image::open("./night.png").unwrap();
+    /// bar.set_background_image(Some(image)).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`BarBuilder::background_image`]: struct.BarBuilder.html#method.background_image
+    pub fn set_background_image(&self, image: Option<DynamicImage>) -> Result<()> {
+        self.background.lock().unwrap().image = image;
+        self.redraw_background()
+    }
+
+    // Recreate the background picture and redraw the whole bar
+    fn redraw_background(&self) -> Result<()> {
+        let geometry = self.geometry();
+        self.replace_background(geometry)
+    }
+
+    // Recreate the background picture for a new geometry and redraw the whole bar
+    // The geometry is only updated together with the picture, so rendering never mixes them
+    fn replace_background(&self, geometry: Geometry) -> Result<()> {
+        {
+            let mut background = self.background.lock().unwrap();
+            let x11 = self.x11.as_ref().map(|x11| &**x11);
+            let picture = create_background_picture(&*self.backend, x11, geometry, &background)?;
+            *self.geometry.lock().unwrap() = geometry;
+            self.backend.free_picture(background.picture);
+            background.picture = picture;
+        }
 
+        render::relayout(self)
+    }
+
+    // Propagate event to the component
+    fn propagate_event(&self, event: Event) {
         let components = self.components.lock().unwrap();
-        for component in &(*components) {
-            let geo = component.geometry;
-            if geo.x < x && geo.x as u16 + geo.width > x as u16 {
-                // Change X pos to be relative to the component
-                match event {
-                    Event::ClickEvent(ref mut e) => e.position.x -= geo.x + 1,
-                    Event::MotionEvent(ref mut e) => e.position.x -= geo.x + 1,
-                }
+        if let Some((id, event)) = event.route(&components) {
+            // Propagate the event to the scheduler
+            self.tasks.send(Task::Event(id, event));
+            debug!("Event propagated to component {}", id);
+        }
+    }
 
-                // Propagate the event when there is a listener
-                if let Some(ref interrupt) = component.interrupt {
-                    interrupt.send(event);
-                    debug!("Event propagated to component {}", component.id);
-                }
+    /// Hide the bar.
+    ///
+    /// This unmaps the window and removes its struts, so other windows can use the space of the
+    /// bar. Components are still updated while the bar is hidden, but they are not drawn until
+    /// the bar is shown again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::BarBuilder;
+    ///
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    /// bar.hide();
+    /// ```
+    pub fn hide(&self) {
+        debug!("Hiding bar");
+        self.hidden.store(true, Ordering::SeqCst);
+        if let Some(ref x11) = self.x11 {
+            let res = clear_struts(&x11.conn, x11.window);
+            err!(res, "Unable to clear struts");
+            xcb::unmap_window(&x11.conn, x11.window);
+            x11.conn.flush();
+        }
+    }
 
-                // There can only be one match
-                break;
-            }
+    /// Show the bar after it has been hidden.
+    ///
+    /// This maps the window and restores its struts. Showing a bar that is not hidden has no
+    /// effect.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::BarBuilder;
+    ///
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    /// bar.hide();
+    /// bar.show();
+    /// ```
+    pub fn show(&self) {
+        debug!("Showing bar");
+        self.hidden.store(false, Ordering::SeqCst);
+        if let Some(ref x11) = self.x11 {
+            let res = set_struts(&x11.conn, x11.window, self.geometry(), self.position);
+            err!(res, "Unable to set struts");
+            xcb::map_window(&x11.conn, x11.window);
+            x11.conn.flush();
+        }
+    }
+
+    /// Hide the bar if it is visible, otherwise show it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::BarBuilder;
+    ///
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    /// bar.toggle();
+    /// ```
+    pub fn toggle(&self) {
+        if self.is_hidden() {
+            self.show();
+        } else {
+            self.hide();
         }
     }
 
+    // Check if the bar is currently hidden
+    pub(crate) fn is_hidden(&self) -> bool {
+        self.hidden.load(Ordering::SeqCst)
+    }
+
     /// Add a new component to the bar.
     ///
+    /// This returns a [`ComponentHandle`] which can be used to redraw, message or remove the
+    /// component from any thread.
+    ///
+    /// All components of a bar share a single thread, which waits for their redraw timers, events
+    /// and messages. The methods of a component should not block, since this would delay all
+    /// other components.
+    ///
     /// # Examples
     ///
     /// ```rust,no_run
@@ -205,99 +660,195 @@ impl Bar {
     /// let mut bar = BarBuilder::new().spawn().unwrap();
     /// bar.add(MyComponent);
     /// ```
-    #[allow(unused_mut)]
-    pub fn add<T: 'static + Component + Send>(&mut self, mut component: T) {
+    ///
+    /// [`ComponentHandle`]: struct.ComponentHandle.html
+    pub fn add<T: 'static + Component + Send>(&mut self, component: T) -> ComponentHandle {
+        let info = self.register(&component);
+        let handle = info.handle.clone();
+
+        // Run the component on the scheduler
+        self.tasks.send(Task::Add(Box::new(info), Box::new(component)));
+
+        handle
+    }
+
+    /// Add a new component which is redrawn by a stream to the bar.
+    ///
+    /// This works just like [`Bar::add`], but [`Component::redraw_timer`] is never called.
+    /// Instead the stream of [`AsyncComponent::redraw_stream`] controls when the component is
+    /// redrawn. All streams are driven by the thread which runs the components of the bar, so no
+    /// additional thread is required for every component.
+    ///
+    /// This method is only available with the `futures` feature enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # extern crate leechbar;
+    /// extern crate futures;
+    /// use leechbar::{AsyncComponent, BarBuilder, Component};
+    /// use futures::{stream, Stream};
+    ///
+    /// struct MyComponent;
+    /// impl Component for MyComponent {}
+    /// impl AsyncComponent for MyComponent {
+    ///     fn redraw_stream(&mut self) -> Box<Stream<Item = (), Error = ()> + Send> {
+    ///         Box::new(stream::empty())
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut bar = BarBuilder::new().spawn().unwrap();
+    /// bar.add_async(MyComponent);
+    /// # }
+    /// ```
+    ///
+    /// [`Bar::add`]: struct.Bar.html#method.add
+    /// [`Component::redraw_timer`]: trait.Component.html#method.redraw_timer
+    /// [`AsyncComponent::redraw_stream`]: trait.AsyncComponent.html#method.redraw_stream
+    #[cfg(feature = "futures")]
+    pub fn add_async<T>(&mut self, mut component: T) -> ComponentHandle
+    where
+        T: 'static + AsyncComponent + Send,
+    {
+        let info = self.register(&component);
+        let handle = info.handle.clone();
+
+        // Run the component and its stream on the scheduler
+        let stream = component.redraw_stream();
+        self.tasks.send(Task::AddAsync(Box::new(info), Box::new(component), stream));
+
+        handle
+    }
+
+    // Assign an id to a new component and reserve its place on the bar
+    fn register(&mut self, component: &Component) -> ComponentInfo {
         // Permanent component id
-        let id = component.alignment().id(&mut self.component_ids);
+        let alignment = component.alignment();
+        let id = alignment.id(&mut self.component_ids);
 
         debug!("Adding component {}", id);
 
-        // Register the component
-        let bar_component = BarComponent::new(id, &self.conn);
+        let bar_component = BarComponent::new(id, component.order());
         {
             let mut components = self.components.lock().unwrap();
             (*components).push(bar_component);
         }
 
-        // Start bar thread
-        let bar = self.clone();
-        thread::spawn(move || {
-            // Get the polling receiver from the component
-            let redraw_timer = component.redraw_timer();
-
-            // Start component loop
-            loop {
-                // Check if component should be redrawn
-                if component.update() {
-                    let res = render::render(&bar, &mut component, id);
-                    err!(res, "Component {}", id);
-                }
+        let handle = ComponentHandle {
+            bar: self.unguarded(),
+            id,
+        };
+        ComponentInfo { handle, alignment }
+    }
 
-                // Update the interrupt on the component
-                let (tx, rx) = chan::async();
-                {
-                    let mut components = bar.components.lock().unwrap();
-                    let comp_index = components.binary_search_by_key(&id, |c| c.id).unwrap_or(0);
-                    components[comp_index].interrupt = Some(tx.clone());
-                }
+    /// Get the current content of the bar.
+    ///
+    /// This reads back everything that has been drawn to the bar, including its background and
+    /// all components. The image always has the size of the bar. While the bar is hidden, the
+    /// content of its window is undefined.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error when the content of the window could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::BarBuilder;
+    ///
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    /// let image = bar.snapshot().unwrap();
+    /// ```
+    pub fn snapshot(&self) -> Result<RgbaImage> {
+        let geometry = self.geometry();
+        self.backend.snapshot(geometry.width, geometry.height)
+    }
 
-                // Select between redraw and event receivers
-                // Redraw when requested
-                loop {
-                    chan_select! {
-                        rx.recv() -> event => {
-                            if let Some(event) = event {
-                                debug!("Component {} received event.", id);
-                                if component.event(event) {
-                                    debug!("Component {} requested redraw after event.", id);
-                                    break;
-                                }
-                            }
-                        },
-                        redraw_timer.recv() -> ping => {
-                            if ping.is_some() {
-                                debug!("Component {} requested redraw without event.", id);
-                                break;
-                            } else {
-                                debug!("Component {} disconnected.", id);
-                                return;
-                            }
-                        },
-                    }
-                }
-            }
-        });
+    /// Save the current content of the bar as PNG.
+    ///
+    /// This takes a [`snapshot`] and writes it to the specified path. The file is always encoded
+    /// as PNG, independent of its extension.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error when the snapshot failed or the file could not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::BarBuilder;
+    ///
+    /// let bar = BarBuilder::new().spawn().unwrap();
+    /// bar.save_snapshot("bar.png").unwrap();
+    /// ```
+    ///
+    /// [`snapshot`]: struct.Bar.html#method.snapshot
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let image = self.snapshot()?;
+        let (width, height) = image.dimensions();
+        let file = File::create(path).map_err(|e| Error::io(&e, path))?;
+        PNGEncoder::new(file)
+            .encode(&image.into_raw(), width, height, ColorType::RGBA(8))
+            .map_err(|e| Error::io(&e, path))
     }
 
-    // Composite a picture on top of the background
+    // Copy a picture to the window, replacing what has been drawn there before
     pub(crate) fn composite_picture(
         &self,
         pic: u32,
         srcx: i16,
-        tarx: i16,
-        w: u16,
-        h: u16,
+        srcy: i16,
+        target: Geometry,
     ) -> Result<()> {
-        // Shorten window to make xcb call single-line
-        let win = self.window_pict;
+        let window = self.backend.window_picture();
+        self.backend.copy(pic, srcx, srcy, window, target)
+    }
+
+    // Current geometry of the bar window
+    pub(crate) fn geometry(&self) -> Geometry {
+        *self.geometry.lock().unwrap()
+    }
 
-        // Composite pictures
-        let op = xcb::render::PICT_OP_OVER as u8;
-        xcb::render::composite_checked(&self.conn, op, pic, 0, win, srcx, 0, 0, 0, tarx, 0, w, h)
-            .request_check()
-            .map_err(|e| ErrorKind::XError(format!("Unable to composite picture: {}", e)))?;
+    // Current background picture of the bar
+    pub(crate) fn background(&self) -> u32 {
+        self.background.lock().unwrap().picture
+    }
 
-        Ok(())
+    // Length of the bar along the axis components are stacked on
+    pub(crate) fn length(&self) -> u16 {
+        self.geometry().length(self.position)
+    }
+
+    // Size of the bar across the axis components are stacked on
+    pub(crate) fn thickness(&self) -> u16 {
+        let geometry = self.geometry();
+        if self.position.is_vertical() {
+            geometry.width
+        } else {
+            geometry.height
+        }
     }
 }
 
+// Background of the bar
+// The color and image are kept to recreate the picture when the geometry changes
+pub(crate) struct BarBackground {
+    pub(crate) picture: u32,
+    pub(crate) color: Color,
+    pub(crate) image: Option<DynamicImage>,
+    pub(crate) mode: BackgroundMode,
+    pub(crate) pseudo_transparent: bool,
+}
+
 // Get the 24 and 32 bit image formats
 // Response is Result<(format24, format32)>
-fn image_formats(conn: &Arc<xcb::Connection>) -> (u32, u32) {
+fn image_formats(conn: &Arc<xcb::Connection>) -> Result<(u32, u32)> {
     // Query connection for all available formats
     let formats = xcb::render::query_pict_formats(conn)
         .get_reply()
-        .expect("Unable to query picture formats")
+        .map_err(|e| Error::NoPictureFormat(Some(XError::new("query_pict_formats", &e))))?
         .formats();
 
     let mut format24 = None;
@@ -327,17 +878,36 @@ fn image_formats(conn: &Arc<xcb::Connection>) -> (u32, u32) {
 
     // Error if one of the formats hasn't been found
     match (format24, format32) {
-        (Some(f_24), Some(f_32)) => (f_24.id(), f_32.id()),
-        _ => panic!("Unable to find 32 or 24 depth picture formats"),
+        (Some(f_24), Some(f_32)) => Ok((f_24.id(), f_32.id())),
+        _ => Err(Error::NoPictureFormat(None)),
+    }
+}
+
+// Negotiate the RandR version with the server
+// Servers are allowed to ignore RandR requests of clients which skipped this
+fn query_randr(conn: &Arc<xcb::Connection>) -> Result<()> {
+    let present = conn.get_extension_data(randr::id()).map_or(false, |data| data.present());
+    if !present {
+        return Err(Error::NoRandr);
     }
+
+    // Version 1.3 is required for querying the primary output
+    let reply = randr::query_version(conn, 1, 5)
+        .get_reply()
+        .map_err(|_| Error::NoRandr)?;
+    if (reply.major_version(), reply.minor_version()) < (1, 3) {
+        return Err(Error::NoRandr);
+    }
+
+    Ok(())
 }
 
 // Get information about specified output
 fn screen_info(
     conn: &Arc<xcb::Connection>,
     query_output_name: Option<String>,
-) -> ::std::result::Result<xcb::Reply<xcb::ffi::randr::xcb_randr_get_crtc_info_reply_t>, BarError> {
-    let root = util::screen(conn).expect("Root screen not found").root();
+) -> Result<randr::GetCrtcInfoReply> {
+    let root = util::screen(conn)?.root();
 
     // Return the default screen when no output is specified
     if query_output_name.is_none() {
@@ -345,16 +915,50 @@ fn screen_info(
     }
     let query_output_name = query_output_name.unwrap(); // Safe unwrap
 
+    // If the output name is the requested name, return the dimensions
+    active_outputs(conn, root)?
+        .into_iter()
+        .find(|&(ref output_name, _)| *output_name == query_output_name)
+        .map(|(_, reply)| reply)
+        .ok_or(Error::OutputNotFound)
+}
+
+// Get the names of all outputs with an active crtc
+pub(crate) fn output_names() -> Result<Vec<String>> {
+    // Connect to the X server
+    let conn = xcb::Connection::connect(None).map_err(Error::ConnectionRefused)?;
+    let conn = Arc::new(conn.0);
+    query_randr(&conn)?;
+
+    let root = util::screen(&conn)?.root();
+    let names = active_outputs(&conn, root)?
+        .into_iter()
+        .map(|(output_name, _)| output_name)
+        .collect::<Vec<String>>();
+
+    // Error if there is no output a bar could be placed on
+    if names.is_empty() {
+        Err(Error::OutputNotFound)
+    } else {
+        Ok(names)
+    }
+}
+
+// Get the name and crtc information of every active output
+fn active_outputs(
+    conn: &Arc<xcb::Connection>,
+    root: u32,
+) -> Result<Vec<(String, randr::GetCrtcInfoReply)>> {
     // Load screen resources of the root window
-    // Return result on error
     let res_cookie = randr::get_screen_resources(conn, root);
     let res_reply = res_cookie
         .get_reply()
-        .expect("Unable to get screen resources");
+        .map_err(|e| Error::ScreenResources(XError::new("get_screen_resources", &e)))?;
 
     // Get all crtcs from the reply
     let crtcs = res_reply.crtcs();
 
+    let mut outputs = Vec::new();
     for crtc in crtcs {
         // Get info about crtc
         let crtc_info_cookie = randr::get_crtc_info(conn, *crtc, 0);
@@ -377,57 +981,147 @@ fn screen_info(
                 output_name = String::from_utf8_lossy(output_info_reply.name()).into();
             }
 
-            // If the output name is the requested name, return the dimensions
-            if output_name == query_output_name {
-                return Ok(reply);
-            }
+            outputs.push((output_name, reply));
         }
     }
 
-    Err(BarErrorKind::OutputNotFound.into())
+    Ok(outputs)
 }
 
 // Get information about the primary output
 fn primary_screen_info(
     conn: &Arc<xcb::Connection>,
     root: u32,
-) -> ::std::result::Result<xcb::Reply<xcb::ffi::randr::xcb_randr_get_crtc_info_reply_t>, BarError> {
+) -> Result<randr::GetCrtcInfoReply> {
     // Load primary output
     let output_cookie = randr::get_output_primary(conn, root);
     let output_reply = output_cookie
         .get_reply()
-        .expect("Unable to find primary output");
+        .map_err(|e| primary_output_error("get_output_primary", &e))?;
     let output = output_reply.output();
 
+    // The output is `None` if no output has been set as primary
+    if output == xcb::NONE {
+        return Err(Error::NoPrimaryOutput(None));
+    }
+
     // Get crtc of primary output
     let output_info_cookie = randr::get_output_info(conn, output, 0);
     let output_info_reply = output_info_cookie
         .get_reply()
-        .map_err(|_| BarErrorKind::NoPrimaryOutput)?;
+        .map_err(|e| primary_output_error("get_output_info", &e))?;
     let crtc = output_info_reply.crtc();
 
     // Get info of primary output's crtc
     let crtc_info_cookie = randr::get_crtc_info(conn, crtc, 0);
-    Ok(crtc_info_cookie
+    crtc_info_cookie
         .get_reply()
-        .expect("Unable to get primary output crtc information"))
+        .map_err(|e| primary_output_error("get_crtc_info", &e))
+}
+
+// Keep the error of a failed request for the primary output
+fn primary_output_error(request: &'static str, err: &xcb::GenericError) -> Error {
+    Error::NoPrimaryOutput(Some(XError::new(request, err)))
+}
+
+// Get the geometry of the bar on the output
+fn bar_geometry(
+    output: Geometry,
+    position: Position,
+    height: u16,
+    width: Option<BarWidth>,
+    margins: (u16, u16),
+) -> Geometry {
+    let (hmargin, vmargin) = margins;
+
+    // Get the space available along the screen edge
+    let (start, output_length, margin) = if position.is_vertical() {
+        (output.y, output.height, vmargin)
+    } else {
+        (output.x, output.width, hmargin)
+    };
+    let available = output_length.saturating_sub(margin.saturating_mul(2));
+
+    // Get the length of the bar, it is centered if it does not fill the available space
+    let length = match width {
+        Some(BarWidth::Pixels(pixels)) => cmp::min(pixels, available),
+        Some(BarWidth::Percent(percent)) => {
+            // Limit the length before converting it back, so it can't be truncated
+            let percent = u32::from(cmp::min(percent, 100));
+            let length = u32::from(output_length) * percent / 100;
+            cmp::min(length, u32::from(available)) as u16
+        }
+        None => available,
+    };
+    let offset = start + (margin + (available - length) / 2) as i16;
+
+    match position {
+        Position::Top => Geometry::new(offset, output.y + vmargin as i16, length, height),
+        Position::Bottom => {
+            let y = output.y + output.height as i16 - vmargin as i16 - height as i16;
+            Geometry::new(offset, y, length, height)
+        }
+        Position::Left => Geometry::new(output.x + hmargin as i16, offset, height, length),
+        Position::Right => {
+            let x = output.x + output.width as i16 - hmargin as i16 - height as i16;
+            Geometry::new(x, offset, height, length)
+        }
+    }
 }
 
 // Create a new window and set all required window parameters to make it a bar
 fn create_window(
     conn: &Arc<xcb::Connection>,
     geometry: Geometry,
+    position: Position,
     background_color: Color,
     window_title: &[u8],
-) -> ::std::result::Result<u32, BarError> {
+    argb_visual: Option<u32>,
+) -> Result<(u32, Option<u32>)> {
     // Get screen of connection
-    let screen = util::screen(conn).expect("Root screen not found");
+    let screen = util::screen(conn)?;
+
+    // Use the root visual unless a 32 bit visual has been requested
+    let (depth, visual) = match argb_visual {
+        Some(visual) => (32, visual),
+        None => (xcb::WINDOW_CLASS_COPY_FROM_PARENT as u8, screen.root_visual()),
+    };
+
+    // Only windows with alpha channel use premultiplied colors
+    let pixel = if argb_visual.is_some() {
+        background_color.as_premultiplied_pixel()
+    } else {
+        background_color.into()
+    };
+
+    let mut values = vec![
+        (xcb::CW_BACK_PIXEL, pixel),
+        (
+            xcb::CW_EVENT_MASK,
+            xcb::EVENT_MASK_EXPOSURE | xcb::EVENT_MASK_POINTER_MOTION
+                | xcb::EVENT_MASK_BUTTON_PRESS | xcb::EVENT_MASK_BUTTON_RELEASE
+                | xcb::EVENT_MASK_STRUCTURE_NOTIFY,
+        ),
+        (xcb::CW_OVERRIDE_REDIRECT, 0),
+    ];
+
+    // Windows with a different depth than the root require their own colormap and border
+    let colormap = if argb_visual.is_some() {
+        let colormap = conn.generate_id();
+        let alloc = xcb::COLORMAP_ALLOC_NONE as u8;
+        xcb::create_colormap(conn, alloc, colormap, screen.root(), visual);
+        values.push((xcb::CW_BORDER_PIXEL, 0));
+        values.push((xcb::CW_COLORMAP, colormap));
+        Some(colormap)
+    } else {
+        None
+    };
 
     // Create the window
     let window = conn.generate_id();
     xcb::create_window(
         conn,
-        xcb::WINDOW_CLASS_COPY_FROM_PARENT as u8,
+        depth,
         window,
         screen.root(),
         geometry.x,
@@ -436,99 +1130,307 @@ fn create_window(
         geometry.height,
         0,
         xcb::WINDOW_CLASS_INPUT_OUTPUT as u16,
-        screen.root_visual(),
-        &[
-            (xcb::CW_BACK_PIXEL, background_color.into()),
-            (
-                xcb::CW_EVENT_MASK,
-                xcb::EVENT_MASK_EXPOSURE | xcb::EVENT_MASK_POINTER_MOTION
-                    | xcb::EVENT_MASK_BUTTON_PRESS | xcb::EVENT_MASK_BUTTON_RELEASE,
-            ),
-            (xcb::CW_OVERRIDE_REDIRECT, 0),
-        ],
+        visual,
+        &values,
     );
 
     // Set all window properties
-    let start_x = geometry.x as u32;
-    let end_x = start_x + u32::from(geometry.width) - 1;
-    let height = u32::from(geometry.height);
-    let struts = [0, 0, height, 0, 0, 0, 0, 0, start_x, end_x, 0, 0];
-    set_prop!(conn, window, "_NET_WM_STRUT", &struts[0..4]);
-    set_prop!(conn, window, "_NET_WM_STRUT_PARTIAL", &struts);
-    set_prop!(conn, window, "_NET_WM_WINDOW_TYPE", @atom "_NET_WM_WINDOW_TYPE_DOCK");
-    set_prop!(conn, window, "_NET_WM_STATE", @atom "_NET_WM_STATE_STICKY");
-    set_prop!(conn, window, "_NET_WM_DESKTOP", &[-1]);
-    set_prop!(conn, window, "_NET_WM_NAME", window_title, "UTF8_STRING", 8);
-    set_prop!(conn, window, "WM_NAME", window_title, "STRING", 8);
+    if let Err(e) = set_window_properties(conn, window, geometry, position, window_title) {
+        destroy_window(conn, window, colormap);
+        return Err(e);
+    }
 
     // Request the WM to manage our window.
     xcb::map_window(conn, window);
 
     info!("Created bar window");
 
-    Ok(window)
+    Ok((window, colormap))
 }
 
-// Create the picture that contains the background color/image
-fn create_background_picture(
+// Set the properties required for the WM to treat the window as bar
+fn set_window_properties(
     conn: &Arc<xcb::Connection>,
     window: u32,
-    gcontext: u32,
-    format32: u32,
     geometry: Geometry,
-    bg_color: Color,
-    background_image: Option<DynamicImage>,
-) -> u32 {
-    // Create shorthands for geometry
+    position: Position,
+    window_title: &[u8],
+) -> Result<()> {
+    set_struts(conn, window, geometry, position)?;
+    set_prop!(conn, window, "_NET_WM_WINDOW_TYPE", @atom "_NET_WM_WINDOW_TYPE_DOCK")?;
+    set_prop!(conn, window, "_NET_WM_STATE", @atom "_NET_WM_STATE_STICKY")?;
+    set_prop!(conn, window, "_NET_WM_DESKTOP", &[-1])?;
+    set_prop!(conn, window, "_NET_WM_NAME", window_title, "UTF8_STRING", 8)?;
+    set_prop!(conn, window, "WM_NAME", window_title, "STRING", 8)?;
+    Ok(())
+}
+
+// Destroy a window which has not been handed to the bar yet
+fn destroy_window(conn: &Arc<xcb::Connection>, window: u32, colormap: Option<u32>) {
+    xcb::destroy_window(conn, window);
+    if let Some(colormap) = colormap {
+        xcb::free_colormap(conn, colormap);
+    }
+}
+
+// Create the GC and the picture used for drawing to the window
+fn create_graphics(
+    conn: &Arc<xcb::Connection>,
+    window: u32,
+    window_format: u32,
+) -> Result<(u32, u32)> {
+    // Create a GC with 32 bit depth
+    let gcontext = {
+        // First create a dummy pixmap with 32 bit depth
+        let pix32 = conn.generate_id();
+        xtry!(create_pixmap_checked, conn, 32, pix32, window, 1, 1);
+
+        // Then create a gc from that pixmap
+        let gc = conn.generate_id();
+        let res = xcb::create_gc_checked(conn, gc, pix32, &[]).request_check();
+
+        // Free pixmap after creating the gc
+        xcb::free_pixmap(conn, pix32);
+        res.map_err(|e| XError::new("create_gc", &e))?;
+
+        gc
+    };
+
+    // Create picture for the window
+    let window_pict = conn.generate_id();
+    let res = xcb::render::create_picture_checked(conn, window_pict, window, window_format, &[])
+        .request_check();
+    if let Err(e) = res {
+        xcb::free_gc(conn, gcontext);
+        return Err(XError::new("create_picture", &e).into());
+    }
+
+    Ok((gcontext, window_pict))
+}
+
+// Reserve the space of the bar at the screen edge it is docked to
+// Struts are relative to the root window, not to the output
+fn set_struts(
+    conn: &Arc<xcb::Connection>,
+    window: u32,
+    geometry: Geometry,
+    position: Position,
+) -> Result<()> {
+    let screen = util::screen(conn)?;
+    let screen_size = (screen.width_in_pixels(), screen.height_in_pixels());
+    let struts = struts(geometry, position, screen_size);
+
+    set_prop!(conn, window, "_NET_WM_STRUT", &struts[0..4])?;
+    set_prop!(conn, window, "_NET_WM_STRUT_PARTIAL", &struts)?;
+    Ok(())
+}
+
+// Get the values of `_NET_WM_STRUT_PARTIAL` for a bar on a screen of the specified size
+fn struts(geometry: Geometry, position: Position, screen_size: (u16, u16)) -> [u32; 12] {
+    let (screen_width, screen_height) = (u32::from(screen_size.0), u32::from(screen_size.1));
+    let (start_x, start_y) = (geometry.x as u32, geometry.y as u32);
+    let end_x = start_x + u32::from(geometry.width);
+    let end_y = start_y + u32::from(geometry.height);
+
+    // Bars without width or height must not underflow the last pixel
+    let (last_x, last_y) = (end_x.saturating_sub(1), end_y.saturating_sub(1));
+    match position {
+        Position::Top => [0, 0, end_y, 0, 0, 0, 0, 0, start_x, last_x, 0, 0],
+        Position::Bottom => {
+            let bottom = screen_height.saturating_sub(start_y);
+            [0, 0, 0, bottom, 0, 0, 0, 0, 0, 0, start_x, last_x]
+        }
+        Position::Left => [end_x, 0, 0, 0, start_y, last_y, 0, 0, 0, 0, 0, 0],
+        Position::Right => {
+            let right = screen_width.saturating_sub(start_x);
+            [0, right, 0, 0, 0, 0, start_y, last_y, 0, 0, 0, 0]
+        }
+    }
+}
+
+// Remove all struts so the space of the bar is available to other windows
+fn clear_struts(
+    conn: &Arc<xcb::Connection>,
+    window: u32,
+) -> Result<()> {
+    let struts = [0u32; 12];
+    set_prop!(conn, window, "_NET_WM_STRUT", &struts[0..4])?;
+    set_prop!(conn, window, "_NET_WM_STRUT_PARTIAL", &struts)?;
+    Ok(())
+}
+
+// Create the picture that contains the background color/image
+// The wallpaper can only be used as base layer when the bar is drawn to a window
+fn create_background_picture(
+    backend: &Backend,
+    x11: Option<&X11>,
+    geometry: Geometry,
+    background: &BarBackground,
+) -> Result<u32> {
+    let bg = backend.create_picture(geometry.width, geometry.height)?;
+
+    // Don't leak the picture if drawing failed
+    let res = draw_background(backend, x11, bg, geometry, background);
+    if res.is_err() {
+        backend.free_picture(bg);
+    }
+
+    res.map(|_| bg)
+}
+
+// Draw the background color/image to the background picture
+fn draw_background(
+    backend: &Backend,
+    x11: Option<&X11>,
+    bg: u32,
+    geometry: Geometry,
+    background: &BarBackground,
+) -> Result<()> {
     let (w, h) = (geometry.width, geometry.height);
 
-    // Create a pixmap for creating the picture
-    let pix = conn.generate_id();
-    xcb::create_pixmap_checked(conn, 32, pix, window, w, h)
-        .request_check()
-        .expect("Unable to create pixmap for bg image");
+    // Use the wallpaper as base layer if pseudo transparency is enabled
+    if let (true, Some(x11)) = (background.pseudo_transparent, x11) {
+        let res = composite_wallpaper(x11, bg, geometry);
+        err!(res, "Unable to copy wallpaper to bg picture");
+    }
 
-    // Add the color to the pixmap
-    // Create a GC with the color
-    let col_gc = conn.generate_id();
-    let col = [(xcb::ffi::xproto::XCB_GC_FOREGROUND, bg_color.into())];
-    xcb::create_gc_checked(conn, col_gc, pix, &col)
-        .request_check()
-        .expect("Unable to create background color GC");
+    // Blend the color with the wallpaper
+    backend.fill(bg, background.color, Geometry::new(0, 0, w, h))?;
+
+    // Add image to picture
+    if let Some(ref background_image) = background.image {
+        // Scale the image to the size of the bar
+        if let Some((background_image, (x, y))) = background.mode.apply(background_image, w, h) {
+            // Get width and height for the picture
+            let w = background_image.width() as u16;
+            let h = background_image.height() as u16;
+
+            // Canvert the image to the right format
+            let data = img::convert_image(&background_image);
+            let img_pict = backend.create_image(w, h, &data)?;
+
+            // Blend the image over the color
+            let res = backend.composite(img_pict, 0, 0, bg, Geometry::new(x, y, w, h));
 
-    // Fill the pixmap with the GC color
-    xcb::poly_fill_rectangle_checked(conn, pix, col_gc, &[Rectangle::new(0, 0, w, h)])
+            // Free the temporary image picture
+            backend.free_picture(img_pict);
+            res?;
+        }
+    }
+
+    Ok(())
+}
+
+// Copy the part of the wallpaper behind the bar to a picture
+fn composite_wallpaper(x11: &X11, target: u32, geometry: Geometry) -> Result<()> {
+    let conn = &x11.conn;
+
+    // Get the wallpaper from the root window
+    let root = util::screen(conn)?.root();
+    let wallpaper = wallpaper_pixmap(conn, root).ok_or(Error::NoWallpaper)?;
+
+    // Create a picture for the wallpaper
+    let wallpaper_pict = conn.generate_id();
+    xtry!(@render create_picture_checked, conn, wallpaper_pict, wallpaper, x11.format24, &[]);
+
+    // Copy the area covered by the bar
+    let (x, y, w, h) = (geometry.x, geometry.y, geometry.width, geometry.height);
+    let (op, src) = (xcb::render::PICT_OP_SRC as u8, wallpaper_pict);
+    let res = xcb::render::composite_checked(conn, op, src, 0, target, x, y, 0, 0, 0, 0, w, h)
         .request_check()
-        .expect("Unable to fill background pixmap with GC color");
+        .map_err(|e| XError::new("composite", &e).into());
+
+    xcb::render::free_picture(conn, wallpaper_pict);
+    res
+}
 
-    // Free gc after filling the rectangle
-    xcb::free_gc(conn, col_gc);
+// Get the pixmap of the wallpaper set by the root window's wallpaper setter
+fn wallpaper_pixmap(conn: &Arc<xcb::Connection>, root: u32) -> Option<u32> {
+    for name in &WALLPAPER_ATOMS {
+        // Skip the atom if it does not exist
+        let atom = match xcb::intern_atom(conn, true, name).get_reply() {
+            Ok(atom) if atom.atom() != xcb::ATOM_NONE => atom.atom(),
+            _ => continue,
+        };
+
+        // Get the pixmap stored in the property
+        let pixmap_type = xcb::ATOM_PIXMAP;
+        let reply = xcb::get_property(conn, false, root, atom, pixmap_type, 0, 1).get_reply();
+        if let Ok(reply) = reply {
+            if let Some(&pixmap) = reply.value::<u32>().first() {
+                return Some(pixmap);
+            }
+        }
+    }
 
-    // Add image to pixmap
-    if let Some(background_image) = background_image {
-        // Get width and height for the picture
-        let w = background_image.width() as u16;
-        let h = background_image.height() as u16;
+    None
+}
 
-        // Canvert the image to the right format
-        let data = img::convert_image(&background_image);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    // 200x20 bar on a 200x100 raster
+    fn bar(raster: &Raster, color: Color) -> Bar {
+        BarBuilder::new()
+            .height(20)
+            .background_color(color)
+            .spawn_headless(raster)
+            .unwrap()
+    }
 
-        // Copy image data to pixmap
-        xcb::put_image_checked(conn, 2u8, pix, gcontext, w, h, 0, 0, 0, 32, &data)
-            .request_check()
-            .expect("Unable to copy image to bg pixmap");
+    #[test]
+    fn struts_of_top_bar() {
+        let geometry = Geometry::new(100, 0, 800, 30);
+        let struts = struts(geometry, Position::Top, (1000, 500));
+        assert_eq!(struts, [0, 0, 30, 0, 0, 0, 0, 0, 100, 899, 0, 0]);
     }
 
-    // Create new picture from pixmap
-    let bg = conn.generate_id();
-    xcb::render::create_picture_checked(conn, bg, pix, format32, &[])
-        .request_check()
-        .expect("Unable to create bg picture");
+    #[test]
+    fn struts_without_height() {
+        let geometry = Geometry::new(0, 0, 0, 0);
+        let struts = struts(geometry, Position::Top, (1000, 500));
+        assert_eq!(struts, [0; 12]);
+    }
 
-    // Free the unneeded pixmap
-    xcb::free_pixmap_checked(conn, pix)
-        .request_check()
-        .expect("Unable to free temporary bg pixmap");
+    #[test]
+    fn width_percent_is_capped() {
+        let output = Geometry::new(0, 0, 40000, 500);
+        let width = Some(BarWidth::Percent(200));
+        let geometry = bar_geometry(output, Position::Top, 30, width, (0, 0));
+        assert_eq!(geometry, Geometry::new(0, 0, 40000, 30));
+    }
+
+    #[test]
+    fn snapshot_is_rgba() {
+        let raster = Raster::new(200, 100);
+        let bar = bar(&raster, Color::new(255, 128, 0, 255));
+
+        let snapshot = bar.snapshot().unwrap();
+        assert_eq!(snapshot.dimensions(), (200, 20));
+        assert_eq!(*snapshot.get_pixel(5, 5), Rgba([255, 128, 0, 255]));
+    }
+
+    #[test]
+    fn snapshot_is_not_premultiplied() {
+        let raster = Raster::new(200, 100);
+        let bar = bar(&raster, Color::new(255, 0, 0, 128));
 
-    bg
+        let snapshot = bar.snapshot().unwrap();
+        assert_eq!(*snapshot.get_pixel(5, 5), Rgba([255, 0, 0, 128]));
+    }
+
+    #[test]
+    fn save_snapshot_error_has_path() {
+        let raster = Raster::new(200, 100);
+        let bar = bar(&raster, Color::new(0, 0, 255, 255));
+        match bar.save_snapshot("/nonexistent/leechbar/bar.png") {
+            Err(Error::Io(kind, message)) => {
+                assert_eq!(kind, ::std::io::ErrorKind::NotFound);
+                assert!(message.starts_with("/nonexistent/leechbar/bar.png: "));
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
 }
@@ -1,6 +1,9 @@
+use util::background_mode::BackgroundMode;
+use util::position::Position;
 use image::DynamicImage;
+use backend::raster::Raster;
 use util::color::Color;
-use error::BarError;
+use error::*;
 use bar;
 
 /// The bar configuration.
@@ -13,7 +16,7 @@ use bar;
 /// Basic usage:
 ///
 /// ```rust,no_run
-/// use leechbar::{BarBuilder, Color};
+/// use leechbar::{BarBuilder, Color, Position};
 ///
 /// // All method calls that take parameters are optional
 /// BarBuilder::new()
@@ -21,6 +24,7 @@ use bar;
 ///     .foreground_color(Color::new(0, 255, 0, 255))
 ///     .font("Fira Mono Medium 14")
 ///     .output("DVI-1")
+///     .position(Position::Bottom)
 ///     .name("MyBar")
 ///     .height(30)
 ///     .spawn()
@@ -31,13 +35,19 @@ use bar;
 #[derive(Clone)]
 pub struct BarBuilder {
     pub(crate) background_image: Option<DynamicImage>,
+    pub(crate) background_mode: BackgroundMode,
     pub(crate) background_color: Color,
     pub(crate) foreground_color: Color,
     pub(crate) output: Option<String>,
+    pub(crate) position: Position,
     pub(crate) font: Option<String>,
     pub(crate) name: String,
     pub(crate) height: u16,
+    pub(crate) width: Option<BarWidth>,
+    pub(crate) margins: (u16, u16),
     pub(crate) text_yoffset: i16,
+    pub(crate) transparent: bool,
+    pub(crate) pseudo_transparent: bool,
     _new_lock: (),
 }
 
@@ -91,9 +101,8 @@ impl BarBuilder {
 
     /// Change the default background image.
     ///
-    /// This takes an image and sets it as the default background for the bar. The image is not
-    /// resized or modified in any way, so it is required to manually adjust it to fit the
-    /// specified bar geometry.
+    /// This takes an image and sets it as the default background for the bar. The image is
+    /// scaled to the bar geometry according to the [`background_mode`].
     ///
     /// **Default:** No background image.
     ///
@@ -109,11 +118,29 @@ impl BarBuilder {
     /// let builder = BarBuilder::new().background_image(image);
     /// # }
     /// ```
+    ///
+    /// [`background_mode`]: struct.BarBuilder.html#method.background_mode
     pub fn background_image(mut self, image: DynamicImage) -> Self {
         self.background_image = Some(image);
         self
     }
 
+    /// Change how the background image is scaled to fit the bar.
+    ///
+    /// **Default:** [`BackgroundMode::Unscaled`](enum.BackgroundMode.html#variant.Unscaled)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::{BackgroundMode, BarBuilder};
+    ///
+    /// let builder = BarBuilder::new().background_mode(BackgroundMode::Tile);
+    /// ```
+    pub fn background_mode(mut self, mode: BackgroundMode) -> Self {
+        self.background_mode = mode;
+        self
+    }
+
     /// Change the default name of the bar.
     ///
     /// This name is used by your Window Manager.
@@ -152,7 +179,8 @@ impl BarBuilder {
 
     /// Change the default height of the bar.
     ///
-    /// This specifies the vertical height used in pixels.
+    /// This specifies the vertical height used in pixels. For bars docked to the left or right
+    /// screen edge, this is the horizontal width instead.
     ///
     /// **Default:** `30`
     ///
@@ -168,6 +196,85 @@ impl BarBuilder {
         self
     }
 
+    /// Change the width of the bar in pixels.
+    ///
+    /// This specifies the horizontal width used in pixels. For bars docked to the left or right
+    /// screen edge, this is the vertical height instead. Bars which do not fill the whole output
+    /// are centered along the screen edge.
+    ///
+    /// **Default:** Full width of the output.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::BarBuilder;
+    ///
+    /// let builder = BarBuilder::new().width(800);
+    /// ```
+    pub fn width(mut self, width: u16) -> Self {
+        self.width = Some(BarWidth::Pixels(width));
+        self
+    }
+
+    /// Change the width of the bar as percentage of the output.
+    ///
+    /// This works like [`width`], but the width is relative to the size of the output. Values
+    /// above `100` are treated as `100`.
+    ///
+    /// **Default:** Full width of the output.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::BarBuilder;
+    ///
+    /// let builder = BarBuilder::new().width_percent(80);
+    /// ```
+    ///
+    /// [`width`]: struct.BarBuilder.html#method.width
+    pub fn width_percent(mut self, percent: u8) -> Self {
+        self.width = Some(BarWidth::Percent(percent));
+        self
+    }
+
+    /// Change the horizontal margin between the bar and the edges of the output.
+    ///
+    /// This adds a gap in pixels to the left and right of horizontal bars. For bars docked to
+    /// the left or right screen edge, this is the gap between the bar and the screen edge.
+    ///
+    /// **Default:** `0`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::BarBuilder;
+    ///
+    /// let builder = BarBuilder::new().horizontal_margin(10);
+    /// ```
+    pub fn horizontal_margin(mut self, margin: u16) -> Self {
+        self.margins.0 = margin;
+        self
+    }
+
+    /// Change the vertical margin between the bar and the edges of the output.
+    ///
+    /// This adds a gap in pixels between horizontal bars and the screen edge. For bars docked to
+    /// the left or right screen edge, this is the gap above and below the bar.
+    ///
+    /// **Default:** `0`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::BarBuilder;
+    ///
+    /// let builder = BarBuilder::new().vertical_margin(10);
+    /// ```
+    pub fn vertical_margin(mut self, margin: u16) -> Self {
+        self.margins.1 = margin;
+        self
+    }
+
     /// Change the default output the bar should be displayed on.
     ///
     /// This uses RANDR to get the output with the specified name. An example value for a DVI
@@ -189,6 +296,25 @@ impl BarBuilder {
         self
     }
 
+    /// Change the screen edge the bar is docked to.
+    ///
+    /// The bar is placed at this edge of the selected output and the struts are set accordingly,
+    /// so the Window Manager reserves the space for the bar.
+    ///
+    /// **Default:** [`Position::Top`](enum.Position.html#variant.Top)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::{BarBuilder, Position};
+    ///
+    /// let builder = BarBuilder::new().position(Position::Bottom);
+    /// ```
+    pub fn position(mut self, position: Position) -> Self {
+        self.position = position;
+        self
+    }
+
     /// Change the default vertical text offset of the bar.
     /// Positive values move the text downwards.
     ///
@@ -208,10 +334,58 @@ impl BarBuilder {
         self
     }
 
+    /// Enable real transparency for the bar window.
+    ///
+    /// This creates the window with a 32 bit visual, so the alpha channel of the background
+    /// color, the background image and all components is preserved. A running compositor is
+    /// required for the transparency to be visible.
+    ///
+    /// **Default:** `false`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::{BarBuilder, Color};
+    ///
+    /// let builder = BarBuilder::new()
+    ///     .background_color(Color::new(0, 0, 0, 128))
+    ///     .transparent(true);
+    /// ```
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Use the wallpaper as base layer of the bar's background.
+    ///
+    /// This fakes transparency without a compositor by copying the part of the wallpaper behind
+    /// the bar. The background color and image are drawn on top of it, so they should be at
+    /// least partially transparent. The wallpaper is read from the `_XROOTPMAP_ID` or
+    /// `ESETROOT_PMAP_ID` property of the root window and updated whenever it changes.
+    ///
+    /// **Default:** `false`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::{BarBuilder, Color};
+    ///
+    /// let builder = BarBuilder::new()
+    ///     .background_color(Color::new(0, 0, 0, 128))
+    ///     .pseudo_transparent(true);
+    /// ```
+    pub fn pseudo_transparent(mut self, pseudo_transparent: bool) -> Self {
+        self.pseudo_transparent = pseudo_transparent;
+        self
+    }
+
     /// Spawn the bar with the currently configured settings.
     ///
     /// This creates a window and registers it as a bar on Xorg.
     ///
+    /// If anything goes wrong while setting up the bar, an [`Error`] describing the failure is
+    /// returned.
+    ///
     /// # Examples
     ///
     /// ```rust,no_run
@@ -219,24 +393,102 @@ impl BarBuilder {
     ///
     /// let bar = BarBuilder::new().spawn().unwrap();
     /// ```
-    pub fn spawn(self) -> Result<bar::Bar, BarError> {
+    ///
+    /// [`Error`]: enum.Error.html
+    pub fn spawn(self) -> Result<bar::Bar> {
         let bar = bar::Bar::new(self)?;
         Ok(bar)
     }
+
+    /// Spawn the bar without a connection to the X.Org server.
+    ///
+    /// Instead of creating a window, the bar is drawn to the [`Raster`], which acts as the output
+    /// the bar is placed on. The content of the bar can be read using [`Raster::image`], which
+    /// makes this useful for testing components without a display.
+    ///
+    /// Since there is no window, the bar does not receive any events. The event loop only waits
+    /// until [`Bar::quit`] is called.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::{BarBuilder, Raster};
+    ///
+    /// let raster = Raster::new(1920, 1080);
+    /// let bar = BarBuilder::new().spawn_headless(&raster).unwrap();
+    /// ```
+    ///
+    /// [`Raster`]: struct.Raster.html
+    /// [`Raster::image`]: struct.Raster.html#method.image
+    /// [`Bar::quit`]: struct.Bar.html#method.quit
+    pub fn spawn_headless(self, raster: &Raster) -> Result<bar::Bar> {
+        bar::Bar::headless(self, raster)
+    }
+
+    /// Spawn one bar on every active output with the currently configured settings.
+    ///
+    /// This ignores the output set with [`output`] and creates a separate window for every
+    /// output that is currently connected and enabled. Every bar has its own event loop, so all
+    /// but one of them have to be started on a separate thread.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::{BarBuilder, Component};
+    /// use std::thread;
+    ///
+    /// struct MyComponent;
+    /// impl Component for MyComponent {}
+    ///
+    /// let bars = BarBuilder::new().spawn_per_output().unwrap();
+    ///
+    /// // Add a component to every bar and start its event loop
+    /// let handles = bars.into_iter()
+    ///     .map(|mut bar| {
+    ///         bar.add(MyComponent);
+    ///         thread::spawn(move || bar.start_event_loop())
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// for handle in handles {
+    ///     handle.join().unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`output`]: struct.BarBuilder.html#method.output
+    pub fn spawn_per_output(self) -> Result<Vec<bar::Bar>> {
+        bar::output_names()?
+            .into_iter()
+            .map(|output| self.clone().output(output).spawn())
+            .collect()
+    }
 }
 
 impl Default for BarBuilder {
     fn default() -> Self {
         BarBuilder {
             background_image: None,
+            background_mode: BackgroundMode::default(),
             background_color: Color::new(0, 0, 0, 255),
             foreground_color: Color::new(255, 255, 255, 255),
             output: None,
+            position: Position::default(),
             name: "leechbar".into(),
             font: None,
             height: 30,
+            width: None,
+            margins: (0, 0),
             text_yoffset: 0,
+            transparent: false,
+            pseudo_transparent: false,
             _new_lock: (),
         }
     }
 }
+
+// Width of the bar along the screen edge
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum BarWidth {
+    Pixels(u16),
+    Percent(u8),
+}
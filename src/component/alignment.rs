@@ -1,4 +1,6 @@
 /// Alignment inside a container.
+///
+/// On vertical bars `LEFT` is the top and `RIGHT` is the bottom of the container.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Alignment {
     LEFT,
@@ -7,12 +9,12 @@ pub enum Alignment {
 }
 
 impl Alignment {
-    // Calculate the x-offset of a component based on its alignment
-    pub(crate) fn x_offset(&self, comp_width: u16, width: u16) -> i16 {
+    // Calculate the offset of a component based on its alignment
+    pub(crate) fn offset(&self, comp_length: u16, length: u16) -> i16 {
         match *self {
             Alignment::LEFT => 0,
-            Alignment::CENTER => (f64::from(comp_width) / 2. - f64::from(width) / 2.) as i16,
-            Alignment::RIGHT => (comp_width - width) as i16,
+            Alignment::CENTER => (f64::from(comp_length) / 2. - f64::from(length) / 2.) as i16,
+            Alignment::RIGHT => (i32::from(comp_length) - i32::from(length)) as i16,
         }
     }
 
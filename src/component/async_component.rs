@@ -0,0 +1,105 @@
+use futures::Stream;
+use component::Component;
+
+// Stream which requests a redraw of a component with every item
+pub(crate) type RedrawStream = Box<Stream<Item = (), Error = ()> + Send>;
+
+/// Trait for components which are redrawn by a stream.
+///
+/// This is an alternative to [`Component::redraw_timer`] for components which are driven by
+/// asynchronous sources. Async components are added to the bar using [`Bar::add_async`].
+///
+/// All streams of a bar are polled on the same thread that runs all components. The stream is
+/// only polled when it has been notified, so it must not block.
+///
+/// This trait is only available with the `futures` feature enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate leechbar;
+/// extern crate futures;
+/// use leechbar::{AsyncComponent, Component};
+/// use futures::sync::mpsc;
+/// use futures::Stream;
+/// use std::thread;
+///
+/// struct MyComponent;
+/// impl Component for MyComponent {}
+///
+/// impl AsyncComponent for MyComponent {
+///     // Redraw every time a message has been sent through the channel
+///     fn redraw_stream(&mut self) -> Box<Stream<Item = (), Error = ()> + Send> {
+///         let (tx, rx) = mpsc::unbounded();
+///
+///         thread::spawn(move || {
+///             let _ = tx.unbounded_send(());
+///         });
+///
+///         Box::new(rx)
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// [`Component::redraw_timer`]: trait.Component.html#method.redraw_timer
+/// [`Bar::add_async`]: struct.Bar.html#method.add_async
+pub trait AsyncComponent: Component {
+    /// The stream which controls the redraw-rate of the component. Every item received from the
+    /// stream redraws the component, if multiple items are ready at once the component is only
+    /// redrawn once. This method is called only once when the component is added to the bar.
+    ///
+    /// After the stream has ended or returned an error, the component is no longer redrawn by
+    /// it. The component still receives events and messages afterwards.
+    fn redraw_stream(&mut self) -> Box<Stream<Item = (), Error = ()> + Send>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chan::{self, Receiver, Sender};
+    use futures::sync::mpsc;
+    use {BarBuilder, Raster};
+
+    // Reports which of its methods have been called by the scheduler
+    struct TestComponent {
+        calls: Sender<&'static str>,
+        stream: Option<mpsc::UnboundedReceiver<()>>,
+    }
+
+    impl Component for TestComponent {
+        fn update(&mut self) -> bool {
+            self.calls.send("update");
+            true
+        }
+
+        fn redraw_timer(&mut self) -> Receiver<()> {
+            self.calls.send("redraw_timer");
+            chan::async().1
+        }
+    }
+
+    impl AsyncComponent for TestComponent {
+        fn redraw_stream(&mut self) -> Box<Stream<Item = (), Error = ()> + Send> {
+            Box::new(self.stream.take().unwrap())
+        }
+    }
+
+    #[test]
+    fn redrawn_by_stream() {
+        let raster = Raster::new(200, 100);
+        let mut bar = BarBuilder::new().height(20).spawn_headless(&raster).unwrap();
+        let (calls, calls_rx) = chan::async();
+        let (stream_tx, stream_rx) = mpsc::unbounded();
+        bar.add_async(TestComponent {
+            calls,
+            stream: Some(stream_rx),
+        });
+
+        // Drawn once after being added, without creating a timer
+        assert_eq!(calls_rx.recv(), Some("update"));
+
+        stream_tx.unbounded_send(()).unwrap();
+        assert_eq!(calls_rx.recv(), Some("update"));
+    }
+}
@@ -1,14 +1,11 @@
 use component::background::Background;
 use component::foreground::Foreground;
 use component::alignment::Alignment;
+use component::width::Width;
 use util::geometry::Geometry;
 use util::color::Color;
-use std::sync::Arc;
-use chan::Sender;
-use event::Event;
 use bar::Bar;
 use error::*;
-use xcb;
 
 #[derive(PartialEq, Clone)]
 pub struct BarComponentCache {
@@ -61,82 +58,70 @@ impl Default for BarComponentCache {
 }
 
 // A component currently stored in the bar
+// The last state of the component is kept for redrawing it when other components change
 pub struct BarComponent {
     pub id: u32,
+    pub order: i32,
     pub picture: u32,
     pub geometry: Geometry,
-    pub interrupt: Option<Sender<Event>>,
+    pub width: Width,
+    pub length: u16,
+    pub background: Background,
+    pub foreground: Foreground,
     pub bg_cache: BarComponentCache,
     pub fg_cache: BarComponentCache,
 }
 
 impl BarComponent {
     // Creates a new component
-    pub fn new(id: u32, conn: &Arc<xcb::Connection>) -> Self {
-        let picture = conn.generate_id();
+    // The picture is created when the component is rendered for the first time
+    pub fn new(id: u32, order: i32) -> Self {
         BarComponent {
             id,
-            picture,
-            interrupt: None,
+            order,
+            picture: 0,
             geometry: Geometry::default(),
+            width: Width::new(),
+            length: 0,
+            background: Background::new(),
+            foreground: Foreground::new(),
             bg_cache: BarComponentCache::new(),
             fg_cache: BarComponentCache::new(),
         }
     }
 
+    // Position of the component inside its alignment group
+    // Components with the same order keep the order they have been added in
+    pub fn sort_key(&self) -> (i32, u32) {
+        (self.order, self.id)
+    }
+
     // Update a component cached by the bar
     pub fn set_geometry(&mut self, geometry: Geometry) {
         self.geometry = geometry;
     }
 
     // Redraw a component
-    // Copies the pixmap to the window
+    // Copies the picture to the window
     pub fn redraw(&self, bar: &Bar) -> Result<()> {
         // Shorten geometry names
-        let (w, h, x) = (self.geometry.width, self.geometry.height, self.geometry.x);
-
-        // Create an intermediate pixmap
-        let tmp_pix = bar.conn.generate_id();
-        xtry!(
-            create_pixmap_checked,
-            &bar.conn,
-            32,
-            tmp_pix,
-            bar.window,
-            w,
-            h
-        );
-
-        // Clear content of pixmap
-        let rect = &[xcb::Rectangle::new(0, 0, w, h)];
-        xtry!(
-            poly_fill_rectangle_checked,
-            &bar.conn,
-            tmp_pix,
-            bar.gcontext,
-            rect
-        );
+        let (w, h) = (self.geometry.width, self.geometry.height);
+        let (x, y) = (self.geometry.x, self.geometry.y);
+        let backend = &bar.backend;
 
-        // Create picture for intermediate pixmap
-        let tmp_pict = bar.conn.generate_id();
-        xtry!(@render create_picture_checked, &bar.conn, tmp_pict, tmp_pix, bar.format32, &[]);
-
-        // Copy over background
-        let op = xcb::render::PICT_OP_OVER as u8;
+        // Create an intermediate picture
+        let tmp_pict = backend.create_picture(w, h)?;
 
         // Copy the background of the bar to that picture
-        let bg = bar.background;
-        xtry!(@render composite_checked, &bar.conn, op, bg, 0, tmp_pict, x, 0, 0, 0, 0, 0, w, h);
-
-        // Copy the component to the temporary picture
-        let pict = self.picture;
-        xtry!(@render composite_checked, &bar.conn, op, pict, 0, tmp_pict, 0, 0, 0, 0, 0, 0, w, h);
-
-        bar.composite_picture(tmp_pict, 0, x, w, h)?;
-
-        // Free the picture and pixmap
-        xcb::free_pixmap(&bar.conn, tmp_pix);
-        xcb::render::free_picture(&bar.conn, tmp_pict);
-        Ok(())
+        // Then copy the component to the temporary picture
+        let area = Geometry::new(0, 0, w, h);
+        let res = backend
+            .composite(bar.background(), x, y, tmp_pict, area)
+            .and_then(|_| backend.composite(self.picture, 0, 0, tmp_pict, area))
+            .and_then(|_| bar.composite_picture(tmp_pict, 0, 0, self.geometry));
+
+        // Free the picture
+        backend.free_picture(tmp_pict);
+        res
     }
 }
@@ -0,0 +1,135 @@
+use scheduler::Task;
+use std::any::Any;
+use bar::Bar;
+use render;
+
+/// Handle to a component on the bar.
+///
+/// This is returned by [`Bar::add`] and can be used to redraw, message or remove the component
+/// from any thread. Dropping the handle does not remove the component.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use leechbar::{BarBuilder, Component};
+/// use std::thread;
+///
+/// struct MyComponent;
+/// impl Component for MyComponent {}
+///
+/// let mut bar = BarBuilder::new().spawn().unwrap();
+/// let handle = bar.add(MyComponent);
+///
+/// // Redraw the component from another thread
+/// let redraw_handle = handle.clone();
+/// thread::spawn(move || redraw_handle.request_redraw());
+///
+/// // Remove the component again
+/// handle.remove();
+/// ```
+///
+/// [`Bar::add`]: struct.Bar.html#method.add
+#[derive(Clone)]
+pub struct ComponentHandle {
+    pub(crate) bar: Bar,
+    pub(crate) id: u32,
+}
+
+impl ComponentHandle {
+    /// Redraw the component.
+    ///
+    /// This has the same effect as a message on the component's [`redraw_timer`], so
+    /// [`update`] is called before the component is redrawn. It does not block and does nothing
+    /// after the component has been removed or the bar has been stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::{BarBuilder, Component};
+    ///
+    /// struct MyComponent;
+    /// impl Component for MyComponent {}
+    ///
+    /// let mut bar = BarBuilder::new().spawn().unwrap();
+    /// let handle = bar.add(MyComponent);
+    /// handle.request_redraw();
+    /// ```
+    ///
+    /// [`redraw_timer`]: trait.Component.html#method.redraw_timer
+    /// [`update`]: trait.Component.html#method.update
+    pub fn request_redraw(&self) {
+        self.send_task(Task::Redraw(self.id));
+    }
+
+    /// Send a message to the component.
+    ///
+    /// The message is passed to [`Component::message`] on the thread running the components, which
+    /// decides if the component should be redrawn. It does not block and does nothing after the
+    /// component has been removed or the bar has been stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::{BarBuilder, Component};
+    /// use std::any::Any;
+    ///
+    /// struct MyComponent {
+    ///     volume: u8,
+    /// }
+    ///
+    /// impl Component for MyComponent {
+    ///     fn message(&mut self, message: Box<Any + Send>) -> bool {
+    ///         match message.downcast::<u8>() {
+    ///             Ok(volume) => {
+    ///                 self.volume = *volume;
+    ///                 true
+    ///             }
+    ///             Err(_) => false,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut bar = BarBuilder::new().spawn().unwrap();
+    /// let handle = bar.add(MyComponent { volume: 0 });
+    /// handle.send(50u8);
+    /// ```
+    ///
+    /// [`Component::message`]: trait.Component.html#method.message
+    pub fn send<T: Any + Send>(&self, message: T) {
+        self.send_task(Task::Message(self.id, Box::new(message)));
+    }
+
+    /// Remove the component from the bar.
+    ///
+    /// This stops receiving timer ticks, events and messages for the component and frees its
+    /// picture. The remaining components are moved to fill the gap. Removing a component that
+    /// has already been removed does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use leechbar::{BarBuilder, Component};
+    ///
+    /// struct MyComponent;
+    /// impl Component for MyComponent {}
+    ///
+    /// let mut bar = BarBuilder::new().spawn().unwrap();
+    /// let handle = bar.add(MyComponent);
+    /// handle.remove();
+    /// ```
+    pub fn remove(&self) {
+        debug!("Removing component {}", self.id);
+
+        self.send_task(Task::Remove(self.id));
+
+        let res = render::remove(&self.bar, self.id);
+        err!(res, "Unable to remove component {}", self.id);
+    }
+
+    // Nothing receives tasks after the bar has been stopped, so they would pile up in the channel
+    fn send_task(&self, task: Task) {
+        if !self.bar.is_quitting() {
+            self.bar.tasks.send(task);
+        }
+    }
+}
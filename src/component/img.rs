@@ -5,7 +5,6 @@ use util::geometry::Geometry;
 use std::sync::Arc;
 use error::*;
 use bar::Bar;
-use xcb;
 
 /// A cached image.
 ///
@@ -41,45 +40,15 @@ impl Image {
     /// # }
     /// ```
     pub fn new(bar: &Bar, image: &DynamicImage) -> Result<Self> {
-        let conn = Arc::clone(&bar.conn);
-        let (window, gcontext, format32) = (bar.window, bar.gcontext, bar.format32);
-
         // Create shorthands for geometry
         let (w, h) = (image.width() as u16, image.height() as u16);
 
-        // Create a pixmap for creating the picture
-        let pix = conn.generate_id();
-        xtry!(create_pixmap_checked, &conn, 32, pix, window, w, h);
-
-        // Convert DynamicImage
-        let data = convert_image(image);
-
-        // Copy image data to pixmap
-        xtry!(
-            put_image_checked,
-            &conn,
-            2u8,
-            pix,
-            gcontext,
-            w,
-            h,
-            0,
-            0,
-            0,
-            32,
-            &data
-        );
-
-        // Create new picture from pixmap
-        let picture = conn.generate_id();
-        xtry!(@render create_picture_checked, &conn, picture, pix, format32, &[]);
-
-        // Free the unneeded pixmap
-        xcb::free_pixmap(&conn, pix);
+        // Convert DynamicImage and copy it to a new picture
+        let picture = bar.backend.create_image(w, h, &convert_image(image))?;
 
         Ok(Self {
             arc: Arc::new(Picture {
-                conn,
+                backend: Arc::clone(&bar.backend),
                 xid: picture,
                 geometry: Geometry::new(0, 0, w, h),
             }),
@@ -124,11 +93,15 @@ pub fn convert_image(image: &DynamicImage) -> Vec<u8> {
     let mut image = image.to_rgba();
 
     // Correct channels to fit xorg layout
+    // The color channels are premultiplied with the alpha
     for pixel in image.pixels_mut() {
         let channels = pixel.channels_mut();
-        let tmp0 = channels[2];
-        let tmp2 = channels[0];
+        let alpha = u32::from(channels[3]);
+        let premultiply = |channel: u8| (u32::from(channel) * alpha / 255) as u8;
+        let tmp0 = premultiply(channels[2]);
+        let tmp2 = premultiply(channels[0]);
         channels[0] = tmp0;
+        channels[1] = premultiply(channels[1]);
         channels[2] = tmp2;
     }
 
@@ -0,0 +1,15 @@
+use component::handle::ComponentHandle;
+use component::alignment::Alignment;
+
+/// Information about a component that has been added to the bar.
+///
+/// This is passed to [`Component::on_added`] when the component is added to the bar.
+///
+/// [`Component::on_added`]: trait.Component.html#method.on_added
+#[derive(Clone)]
+pub struct ComponentInfo {
+    /// Handle for redrawing, messaging or removing the component.
+    pub handle: ComponentHandle,
+    /// Alignment the component has been added with.
+    pub alignment: Alignment,
+}
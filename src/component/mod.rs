@@ -2,17 +2,25 @@ pub mod bar_component;
 pub mod foreground;
 pub mod background;
 pub mod alignment;
+pub mod handle;
+pub mod info;
 pub mod picture;
 pub mod width;
 pub mod text;
 pub mod img;
+#[cfg(feature = "futures")]
+pub mod async_component;
 
 use chan::{self, Receiver};
 use component::foreground::Foreground;
 use component::background::Background;
 use component::alignment::Alignment;
 use component::width::Width;
+use component::info::ComponentInfo;
+use util::geometry::Geometry;
 use event::Event;
+use bar::Bar;
+use std::any::Any;
 
 /// Trait for creating custom components.
 ///
@@ -84,10 +92,49 @@ pub trait Component {
         false
     }
 
+    /// This is called whenever a message is sent to the component using its
+    /// [`ComponentHandle`].
+    ///
+    /// The return value is used to check if the component is supposed to be redrawn after the
+    /// message has been processed.
+    ///
+    /// **Default:** `false`, do nothing when a message is received.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::Component;
+    /// use std::any::Any;
+    ///
+    /// struct MyComponent {
+    ///     content: String,
+    /// }
+    ///
+    /// impl Component for MyComponent {
+    ///     // Redraw whenever a new content is received
+    ///     fn message(&mut self, message: Box<Any + Send>) -> bool {
+    ///         if let Ok(content) = message.downcast::<String>() {
+    ///             self.content = *content;
+    ///             return true;
+    ///         }
+    ///         false
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`ComponentHandle`]: struct.ComponentHandle.html
+    fn message(&mut self, _message: Box<Any + Send>) -> bool {
+        false
+    }
+
     /// This method controls the redraw-rate of the component. Every time the `Receiver` receives
     /// any message, the component is redrawn. This method is called only once when the component
     /// is added to the bar, dropping the `Sender` will stop the component from being redrawn
-    /// without removing the current state from the bar.
+    /// without removing the current state from the bar. The component still receives events and
+    /// messages afterwards.
+    ///
+    /// The [`timer`] module provides timers for redrawing in an interval or on wall-clock
+    /// boundaries, without spawning a thread for every component.
     ///
     /// **Default:** Sender dropped immediately, component is drawn only once.
     ///
@@ -96,26 +143,20 @@ pub trait Component {
     /// ```rust
     /// # extern crate leechbar;
     /// extern crate chan;
-    /// use leechbar::Component;
+    /// use leechbar::{timer, Component};
     /// use std::time::Duration;
-    /// use std::thread;
     ///
     /// struct MyComponent;
     /// impl Component for MyComponent {
     ///     // Redraw this component every 5 seconds
     ///     fn redraw_timer(&mut self) -> chan::Receiver<()> {
-    ///         let (tx, rx) = chan::sync(0);
-    ///
-    ///         thread::spawn(move || loop {
-    ///             thread::sleep(Duration::from_secs(5));
-    ///             tx.send(());
-    ///         });
-    ///
-    ///         rx
+    ///         timer::every(Duration::from_secs(5))
     ///     }
     /// }
     /// # fn main() {}
     /// ```
+    ///
+    /// [`timer`]: timer/index.html
     fn redraw_timer(&mut self) -> Receiver<()> {
         let (_tx, rx) = chan::sync(0);
         rx
@@ -187,6 +228,32 @@ pub trait Component {
         Alignment::CENTER
     }
 
+    /// The position of the component inside its alignment group.
+    ///
+    /// Components with a lower order are placed before components with a higher order, so a
+    /// component added later can appear before existing ones. Components with the same order are
+    /// placed in the order they have been added. Like the alignment, this is only called once when
+    /// the component is added to the bar.
+    ///
+    /// **Default:** `0`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::Component;
+    ///
+    /// struct MyComponent;
+    /// impl Component for MyComponent {
+    ///     // Put the component before all components with the default order
+    ///     fn order(&self) -> i32 {
+    ///         -1
+    ///     }
+    /// }
+    /// ```
+    fn order(&self) -> i32 {
+        0
+    }
+
     /// The width of the component.
     ///
     /// **Default:** No width restrictions.
@@ -207,4 +274,84 @@ pub trait Component {
     fn width(&self) -> Width {
         Width::new()
     }
+
+    /// This is called once on the thread running the components after the component has been
+    /// added to the bar, before it is drawn for the first time. Redraws and messages requested
+    /// through the handle are handled right after this returns.
+    ///
+    /// The [`ComponentInfo`] contains a handle to the component, which can be stored to redraw
+    /// or message the component later on.
+    ///
+    /// **Default:** Do nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::{Bar, Component, ComponentHandle, ComponentInfo};
+    ///
+    /// struct MyComponent {
+    ///     handle: Option<ComponentHandle>,
+    /// }
+    ///
+    /// impl Component for MyComponent {
+    ///     // Store the handle for removing the component later
+    ///     fn on_added(&mut self, _bar: &Bar, info: ComponentInfo) {
+    ///         self.handle = Some(info.handle);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`ComponentInfo`]: struct.ComponentInfo.html
+    fn on_added(&mut self, _bar: &Bar, _info: ComponentInfo) {}
+
+    /// This is called whenever the space allocated to the component on the bar has changed.
+    ///
+    /// The geometry is relative to the top-left of the bar. Components which have been hidden
+    /// because there is not enough space on the bar receive a geometry without any length along
+    /// the bar, so its width is zero, or its height for bars docked to the left or right screen
+    /// edge. The return value is used to check if the component is supposed to be redrawn,
+    /// for example to fit its content into the new size. The component is redrawn without calling
+    /// [`update`](trait.Component.html#method.update) first.
+    ///
+    /// **Default:** `false`, do nothing when the geometry changes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::{Component, Geometry};
+    ///
+    /// struct MyComponent {
+    ///     width: u16,
+    /// }
+    ///
+    /// impl Component for MyComponent {
+    ///     // Redraw the content when the width has changed
+    ///     fn on_geometry_changed(&mut self, geometry: Geometry) -> bool {
+    ///         let changed = geometry.width != self.width;
+    ///         self.width = geometry.width;
+    ///         changed
+    ///     }
+    /// }
+    /// ```
+    fn on_geometry_changed(&mut self, _geometry: Geometry) -> bool {
+        false
+    }
+
+    /// This is called once when the component is removed from the bar or the bar is shut down.
+    ///
+    /// **Default:** Do nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::Component;
+    ///
+    /// struct MyComponent;
+    /// impl Component for MyComponent {
+    ///     fn on_removed(&mut self) {
+    ///         println!("Goodbye!");
+    ///     }
+    /// }
+    /// ```
+    fn on_removed(&mut self) {}
 }
@@ -1,10 +1,10 @@
 use util::geometry::Geometry;
+use backend::Backend;
 use std::sync::Arc;
-use xcb;
 
 // Picture with known size
 pub struct Picture {
-    pub(crate) conn: Arc<xcb::Connection>,
+    pub(crate) backend: Arc<Backend>,
     pub(crate) geometry: Geometry,
     pub(crate) xid: u32,
 }
@@ -12,6 +12,6 @@ pub struct Picture {
 // Drop picture when it goes out of scope
 impl Drop for Picture {
     fn drop(&mut self) {
-        xcb::render::free_picture(&self.conn, self.xid);
+        self.backend.free_picture(self.xid);
     }
 }
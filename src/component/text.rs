@@ -1,15 +1,13 @@
-use cairo::{Context, Format, ImageSurface, Surface};
+use cairo::{Context, Format, ImageSurface};
 use pango::{FontDescription, Layout, LayoutExt};
 use component::picture::Picture;
 use util::geometry::Geometry;
 use util::color::Color;
+use backend::raster;
 use std::sync::Arc;
 use pangocairo;
-use cairo_sys;
 use bar::Bar;
 use error::*;
-use util;
-use xcb;
 
 /// A cached text.
 ///
@@ -49,7 +47,7 @@ impl Text {
         // It's not possible to create an empty text
         // This returns an error if it is attempted
         if content.is_empty() {
-            return Err("Text content empty".into());
+            return Err(Error::EmptyText);
         }
 
         // Get the font
@@ -65,32 +63,20 @@ impl Text {
             &lifetime_elongater
         };
 
-        // Close connection for destructor
-        let conn = Arc::clone(&bar.conn);
-
         // Get width and height for text
-        let (w, h) = (text_width(content, font)?, bar.geometry.height);
-
-        // Create a new pixmap with empty background
-        let pix = conn.generate_id();
-        xtry!(create_pixmap_checked, &conn, 32, pix, bar.window, w, h);
-        let rect = &[xcb::Rectangle::new(0, 0, w, h)];
-        xtry!(poly_fill_rectangle_checked, &conn, pix, bar.gcontext, rect);
-
-        // Create an xcb surface
-        let mut visualtype = find_visualtype32(&util::screen(&conn)?)
-            .ok_or_else(|| ErrorKind::ScreenDepthError(()))?;
-        let surface = unsafe {
-            Surface::from_raw_full(cairo_sys::cairo_xcb_surface_create(
-                conn.get_raw_conn() as *mut cairo_sys::xcb_connection_t,
-                pix,
-                (&mut visualtype.base as *mut xcb::ffi::xcb_visualtype_t)
-                    as *mut cairo_sys::xcb_visualtype_t,
-                i32::from(w),
-                i32::from(h),
-            ))
+        // On vertical bars the text is only as high as its content
+        let (w, text_h) = text_size(content, font)?;
+        let h = if bar.position.is_vertical() {
+            text_h
+        } else {
+            bar.geometry().height
         };
 
+        // Create an image surface with empty background
+        let (surface_w, surface_h) = (i32::from(w), i32::from(h));
+        let mut surface =
+            ImageSurface::create(Format::ARgb32, surface_w, surface_h).map_err(Error::Cairo)?;
+
         // Create context and layout for drawing text
         let context = Context::new(&surface);
         let layout = layout(&context, content, font);
@@ -111,16 +97,16 @@ impl Text {
         // Display text
         pangocairo::functions::show_layout(&context, &layout);
 
-        // Create picture from pixmap
-        let picture = conn.generate_id();
-        xtry!(@render create_picture_checked, &conn, picture, pix, bar.format32, &[]);
-
-        // Free the unneeded pixmap
-        xcb::free_pixmap(&conn, pix);
+        // Copy the rendered text to a new picture
+        // The context needs to be dropped to get exclusive access to the surface data
+        drop(layout);
+        drop(context);
+        let data = raster::surface_data(&mut surface)?;
+        let picture = bar.backend.create_image(w, h, &data)?;
 
         Ok(Self {
             arc: Arc::new(Picture {
-                conn,
+                backend: Arc::clone(&bar.backend),
                 xid: picture,
                 geometry: Geometry::new(0, 0, w, h),
             }),
@@ -128,20 +114,19 @@ impl Text {
     }
 }
 
-// Get the width text will have with the specified font
-fn text_width(text: &str, font: &FontDescription) -> Result<(u16)> {
+// Get the width and height text will have with the specified font
+fn text_size(text: &str, font: &FontDescription) -> Result<(u16, u16)> {
     // Create a dummy surface and context
-    let surface = ImageSurface::create(Format::ARgb32, 0, 0)
-        .map_err(|e| format!("Unable to create dummy layout for font size: {:?}", e))?;
+    let surface = ImageSurface::create(Format::ARgb32, 0, 0).map_err(Error::Cairo)?;
     let context = Context::new(&surface);
 
     // Create the layout
     let layout = layout(&context, text, font);
 
-    // Get the width of the text
-    let width = layout.get_pixel_size().0;
+    // Get the size of the text
+    let (width, height) = layout.get_pixel_size();
 
-    Ok(width as u16)
+    Ok((width as u16, height as u16))
 }
 
 // Create a layout with the font and text
@@ -151,16 +136,3 @@ fn layout(context: &Context, text: &str, font: &FontDescription) -> Layout {
     layout.set_font_description(font);
     layout
 }
-
-// Get the first available visualtype with 32 bit depth
-fn find_visualtype32<'s>(screen: &xcb::Screen<'s>) -> Option<xcb::Visualtype> {
-    for depth in screen.allowed_depths() {
-        if depth.depth() == 32 {
-            let visual = depth.visuals().next();
-            if let Some(visual) = visual {
-                return Some(visual);
-            }
-        }
-    }
-    None
-}
@@ -24,6 +24,9 @@ pub struct Width {
     pub(crate) max: u16,
     pub(crate) ignore_background: bool,
     pub(crate) ignore_foreground: bool,
+    pub(crate) flex: u16,
+    pub(crate) shrink: bool,
+    pub(crate) priority: Option<u8>,
 }
 
 impl Width {
@@ -43,10 +46,14 @@ impl Width {
             max: ::std::u16::MAX,
             ignore_foreground: false,
             ignore_background: false,
+            flex: 0,
+            shrink: false,
+            priority: None,
         }
     }
 
-    /// Set the component to a fixed with. This overrides min, max, background and text width.
+    /// Set the component to a fixed with. This overrides min, max, flex, background and text
+    /// width.
     ///
     /// # Examples
     ///
@@ -88,6 +95,67 @@ impl Width {
         self
     }
 
+    /// Let the component grow into the space which is not used by other components.
+    ///
+    /// The width set by min, background and text is used as the starting point, the space left
+    /// between the alignment groups is then shared between all flexible components relative to
+    /// their weight. Components in the left and right group fill the space next to the center
+    /// group. Components in the center group grow in both directions, so they stay centered. The
+    /// max width is still respected, a weight of `0` disables growing.
+    ///
+    /// This has no effect if a fixed width is set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::Width;
+    ///
+    /// // Takes twice as much of the free space as a component with weight 1
+    /// let width = Width::new().flex(2);
+    /// ```
+    pub fn flex(mut self, weight: u16) -> Self {
+        self.flex = weight;
+        self
+    }
+
+    /// Allow truncating the component when the components don't fit on the bar.
+    ///
+    /// If the left, center and right group would overlap, components with this flag are shrunk
+    /// down to their min width before any component is hidden. The content is cut off based on
+    /// its alignment, text is not ellipsized.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::Width;
+    ///
+    /// // Never shrink the component below 50 pixels
+    /// let width = Width::new().min(50).shrink();
+    /// ```
+    pub fn shrink(mut self) -> Self {
+        self.shrink = true;
+        self
+    }
+
+    /// Allow hiding the component when the components don't fit on the bar.
+    ///
+    /// If the left, center and right group still overlap after shrinking, components with a
+    /// priority are hidden until the rest fits. Components with a lower priority are hidden
+    /// first. Components without a priority are never hidden.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use leechbar::Width;
+    ///
+    /// // Hidden before components with a higher priority
+    /// let width = Width::new().priority(0);
+    /// ```
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
     /// When this is set, the width of the background is ignored.
     /// It is useful if you want to fit a background image to the width of the text.
     ///
@@ -1,120 +1,166 @@
 //! Error Types.
 
-use std::fmt;
+use std::{error, fmt, io};
+use std::path::Path;
+use cairo;
+use xcb;
 
-error_chain! {
-    foreign_links {
-        XcbConnectionError(::xcb::ConnError);
-    }
-
-    errors {
-        /// Unable to find a screen.
-        ///
-        /// This error occurs when the connection was possible, but no screen could be found.
-        XcbNoScreenError(err: ()) {
-            description("No screen found"),
-            display("No screen found"),
-        }
-
-        /// Unable to set a window property.
-        ///
-        /// This returns an XCB error code. These codes are defined [here].
-        ///
-        /// [here]: https://cgit.freedesktop.org/xorg/proto/xproto/tree/X.h#n346
-        XcbPropertyError(code: u8) {
-            description("Unable to set window property"),
-            display("Unable to set window property: '{}'", code),
-        }
-
-        /// Unable to get screen resources.
-        ///
-        /// This returns an XCB error code. These codes are defined [here].
-        ///
-        /// [here]: https://cgit.freedesktop.org/xorg/proto/xproto/tree/X.h#n346
-        XcbScreenResourcesError(code: u8) {
-            description("Unable to get screen resources"),
-            display("Unable to get screen resources: '{}'", code),
-        }
-
-        /// Unable to get primary screen information. This only occurs when not specifying an
-        /// output manually.
-        ///
-        /// This returns an XCB error code. These codes are defined [here].
-        ///
-        /// [here]: https://cgit.freedesktop.org/xorg/proto/xproto/tree/X.h#n346
-        PrimaryScreenInfoError(code: u8) {
-            description("Unable to get primary screen information"),
-            display("Unable to get primary screen information: '{}'", code),
-        }
-
-        /// Indicates an error with an XCB request. This is usually not because of parameters
-        /// specified, but because there was an issue with the X.Org connection.
-        XError(description: String) {
-            description("Unable to send XCB request"),
-            display("Unable to send XCB request: '{}'", description),
-        }
+/// Result type of all fallible operations.
+pub type Result<T> = ::std::result::Result<T, Error>;
 
-        /// The screen does not support a 32 bit visual.
-        ScreenDepthError(arg: ()) {
-            description("Invalid screen depth support"),
-            display("The screen does not support 32 bit depth visuals"),
-        }
-    }
-}
-
-/// Different types of bar creation errors.
+/// Different types of errors.
 ///
-/// These are all the different errors that can occur during the creation of the bar.
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub enum BarErrorKind {
+/// This is the error returned by every fallible function of leechbar, like spawning the bar or
+/// creating a text or an image.
+#[derive(Debug)]
+pub enum Error {
     /// Unable to connect to the X.Org server. Please make sure you are running X.Org and not
-    /// Wayland.
-    ConnectionRefused,
+    /// Wayland. This contains the reason reported by XCB.
+    ConnectionRefused(xcb::ConnError),
+    /// The X.Org server did not report any screen for the connection.
+    NoScreen,
     /// No primary output could be found. This is most likely because you have only one output and
     /// it is not set as primary.
     ///
     /// You can set the `primary` flag on your output using `xrandr --output <OUTPUT> --primary`.
     /// If this does not work, you can set the output directly using
     /// [`output`](struct.BarBuilder.html#method.output).
-    NoPrimaryOutput,
+    ///
+    /// This contains the error of the X.Org server, if one of the requests for the primary output
+    /// failed.
+    NoPrimaryOutput(Option<XError>),
     /// The specified output could not be found. Please make sure the correct name is used. You can
     /// find out the name of your outputs using `xrandr`.
     OutputNotFound,
+    /// Unable to query the outputs of the screen using RandR.
+    ScreenResources(XError),
+    /// The X.Org server does not support version 1.3 of the RandR extension, which is required
+    /// for finding the outputs of the screen.
+    NoRandr,
+    /// The screen does not support 32 bit TrueColor visuals. These are required for transparent
+    /// bars.
+    NoArgbVisual,
+    /// The X.Org server does not support the 24 bit or 32 bit picture formats required for
+    /// rendering the bar. This contains the error of the X.Org server, if the formats could not
+    /// be queried.
+    NoPictureFormat(Option<XError>),
+    /// Unable to intern an atom required for setting the window properties of the bar.
+    InternAtom(XError),
+    /// Pseudo transparency is enabled, but no wallpaper is set on the root window.
+    NoWallpaper,
+    /// It is not possible to create a text without any content.
+    EmptyText,
+    /// Cairo was not able to create or access a surface for rendering.
+    Cairo(cairo::Status),
+    /// The X.Org server returned an error for a request.
+    X(XError),
+    /// Unable to write a file, like saving a snapshot of the bar. This contains the kind of the
+    /// error and a message with the path of the file and the reason reported by the OS.
+    Io(io::ErrorKind, String),
 }
 
-impl BarErrorKind {
-    fn as_str(&self) -> &'static str {
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            BarErrorKind::ConnectionRefused => "Unable to connect to X.Org",
-            BarErrorKind::NoPrimaryOutput => "Unable to find primary output (see docs)",
-            BarErrorKind::OutputNotFound => "Unable to find specified output",
+            Error::ConnectionRefused(ref err) => write!(fmt, "{}: {}", self.as_str(), err),
+            Error::Cairo(status) => write!(fmt, "{}: {:?}", self.as_str(), status),
+            Error::X(ref err)
+            | Error::ScreenResources(ref err)
+            | Error::InternAtom(ref err)
+            | Error::NoPrimaryOutput(Some(ref err))
+            | Error::NoPictureFormat(Some(ref err)) => write!(fmt, "{}: {}", self.as_str(), err),
+            Error::Io(_, ref message) => write!(fmt, "{}: {}", self.as_str(), message),
+            _ => write!(fmt, "{}", self.as_str()),
         }
     }
 }
 
-/// Bar creation error.
-///
-/// This error is returned when anything went wrong during the creation of the bar.
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
-pub struct BarError {
-    /// Different types of bar creation errors.
-    pub kind: BarErrorKind,
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::ConnectionRefused(ref err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
-impl From<BarErrorKind> for BarError {
-    fn from(kind: BarErrorKind) -> BarError {
-        BarError { kind }
+impl Error {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            Error::ConnectionRefused(_) => "Unable to connect to X.Org",
+            Error::NoScreen => "Unable to find root screen",
+            Error::NoPrimaryOutput(_) => "Unable to find primary output (see docs)",
+            Error::OutputNotFound => "Unable to find specified output",
+            Error::ScreenResources(_) => "Unable to get screen resources",
+            Error::NoRandr => "RandR 1.3 is not supported",
+            Error::NoArgbVisual => "Unable to find 32 bit TrueColor visual",
+            Error::NoPictureFormat(_) => "Unable to find 24 or 32 bit picture format",
+            Error::InternAtom(_) => "Unable to intern atom for window property",
+            Error::NoWallpaper => "No wallpaper set on root window",
+            Error::EmptyText => "Text content empty",
+            Error::Cairo(_) => "Unable to create cairo surface",
+            Error::X(_) => "X.Org request failed",
+            Error::Io(..) => "Unable to write file",
+        }
+    }
+
+    // Keep the OS error message and the path of the file
+    pub(crate) fn io(err: &io::Error, path: &Path) -> Self {
+        Error::Io(err.kind(), format!("{}: {}", path.display(), err))
     }
 }
 
-impl fmt::Display for BarError {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", self.kind.as_str())
+impl From<XError> for Error {
+    fn from(err: XError) -> Error {
+        Error::X(err)
     }
 }
 
-impl ::std::error::Error for BarError {
-    fn description(&self) -> &str {
-        self.kind.as_str()
+/// Error returned by the X.Org server.
+///
+/// This contains all information about a failed request the X.Org server provides. The error
+/// codes are defined [here].
+///
+/// [here]: https://cgit.freedesktop.org/xorg/proto/xproto/tree/X.h#n346
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct XError {
+    /// Name of the request that failed.
+    pub request: &'static str,
+    /// The X.Org error code.
+    pub error_code: u8,
+    /// Major opcode of the failed request.
+    pub major_opcode: u8,
+    /// Minor opcode of the failed request. This is only used by extensions.
+    pub minor_opcode: u16,
+    /// ID of the resource that caused the error.
+    pub resource_id: u32,
+}
+
+impl XError {
+    // Extract all information from an XCB error
+    // The `_checked` suffix of the XCB function is not part of the request name
+    pub(crate) fn new(request: &'static str, err: &xcb::GenericError) -> Self {
+        let err = unsafe { &*err.ptr };
+        XError {
+            request: request.trim_right_matches("_checked"),
+            error_code: err.error_code,
+            major_opcode: err.major_code,
+            minor_opcode: err.minor_code,
+            resource_id: err.resource_id,
+        }
+    }
+}
+
+impl fmt::Display for XError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "'{}' failed with error code {} (opcode {}.{}, resource {:#x})",
+            self.request, self.error_code, self.major_opcode, self.minor_opcode, self.resource_id
+        )
     }
 }
@@ -1,7 +1,9 @@
 use xcb::{ButtonPressEvent, MotionNotifyEvent};
+use component::bar_component::BarComponent;
 use util::geometry::Geometry;
 
 /// Event that indicates interaction with the component.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Event {
     /// User clicked on the component.
     ClickEvent(ClickEvent),
@@ -9,6 +11,36 @@ pub enum Event {
     MotionEvent(MotionEvent),
 }
 
+impl Event {
+    // Find the component at the position of the event
+    // The position of the returned event is relative to the top-left of the component
+    pub(crate) fn route(mut self, components: &[BarComponent]) -> Option<(u32, Event)> {
+        let (x, y) = {
+            let position = self.position_mut();
+            (position.x, position.y)
+        };
+
+        let component = components
+            .iter()
+            .find(|component| component.geometry.contains(x, y))?;
+
+        {
+            let position = self.position_mut();
+            position.x -= component.geometry.x;
+            position.y -= component.geometry.y;
+        }
+
+        Some((component.id, self))
+    }
+
+    fn position_mut(&mut self) -> &mut Geometry {
+        match *self {
+            Event::ClickEvent(ref mut e) => &mut e.position,
+            Event::MotionEvent(ref mut e) => &mut e.position,
+        }
+    }
+}
+
 // This implements both button down and up
 impl<'a> From<&'a ButtonPressEvent> for Event {
     fn from(event: &'a ButtonPressEvent) -> Event {
@@ -74,3 +106,70 @@ pub struct MotionEvent {
     /// The position the user moved the mouse to.
     pub position: Geometry,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(id: u32, x: i16, y: i16, width: u16, height: u16) -> BarComponent {
+        let mut component = BarComponent::new(id, 0);
+        component.set_geometry(Geometry::new(x, y, width, height));
+        component
+    }
+
+    fn click(x: i16, y: i16) -> Event {
+        Event::ClickEvent(ClickEvent {
+            button: MouseButton::Left,
+            position: Geometry::new(x, y, 0, 0),
+            released: false,
+        })
+    }
+
+    fn motion(x: i16, y: i16) -> Event {
+        Event::MotionEvent(MotionEvent {
+            position: Geometry::new(x, y, 0, 0),
+        })
+    }
+
+    #[test]
+    fn route_first_pixel() {
+        let components = [component(0, 0, 0, 10, 20), component(1, 10, 0, 10, 20)];
+        assert_eq!(click(0, 0).route(&components), Some((0, click(0, 0))));
+        assert_eq!(click(10, 0).route(&components), Some((1, click(0, 0))));
+    }
+
+    #[test]
+    fn route_last_pixel() {
+        let components = [component(0, 0, 0, 10, 20), component(1, 10, 0, 10, 20)];
+        assert_eq!(click(9, 19).route(&components), Some((0, click(9, 19))));
+        assert_eq!(click(19, 19).route(&components), Some((1, click(9, 19))));
+        assert_eq!(click(20, 19).route(&components), None);
+    }
+
+    #[test]
+    fn route_outside_y_bounds() {
+        let components = [component(0, 0, 0, 10, 20)];
+        assert_eq!(click(5, -1).route(&components), None);
+        assert_eq!(click(5, 20).route(&components), None);
+    }
+
+    #[test]
+    fn route_vertical_bar() {
+        let components = [component(0, 0, 0, 30, 10), component(1, 0, 10, 30, 10)];
+        assert_eq!(click(29, 10).route(&components), Some((1, click(29, 0))));
+        assert_eq!(click(30, 10).route(&components), None);
+    }
+
+    #[test]
+    fn route_skips_hidden_components() {
+        let components = [component(0, 10, 0, 0, 20), component(1, 10, 0, 10, 20)];
+        assert_eq!(click(10, 5).route(&components), Some((1, click(0, 5))));
+    }
+
+    #[test]
+    fn route_motion_event() {
+        let components = [component(0, 5, 0, 10, 20)];
+        assert_eq!(motion(14, 7).route(&components), Some((0, motion(9, 7))));
+        assert_eq!(motion(4, 7).route(&components), None);
+    }
+}
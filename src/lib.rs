@@ -90,13 +90,14 @@
 #![recursion_limit = "1024"]
 
 extern crate cairo;
-extern crate cairo_sys;
 #[macro_use]
 extern crate chan;
-#[macro_use]
-extern crate error_chain;
+#[cfg(feature = "futures")]
+extern crate futures;
 extern crate image;
 #[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate log;
 extern crate pango;
 extern crate pangocairo;
@@ -104,23 +105,34 @@ extern crate xcb;
 
 #[macro_use]
 mod macros;
+mod backend;
 mod component;
 mod error;
 mod builder;
 mod render;
+mod scheduler;
 mod event;
 mod util;
 mod bar;
+pub mod timer;
 
 pub use event::{ClickEvent, Event, MotionEvent, MouseButton};
 pub use component::foreground::Foreground;
 pub use component::background::Background;
 pub use component::alignment::Alignment;
-pub use error::{BarError, BarErrorKind};
+pub use error::{Error, Result, XError};
 pub use component::width::Width;
 pub use component::text::Text;
 pub use component::img::Image;
 pub use component::Component;
+pub use component::handle::ComponentHandle;
+pub use component::info::ComponentInfo;
+#[cfg(feature = "futures")]
+pub use component::async_component::AsyncComponent;
 pub use builder::BarBuilder;
+pub use backend::raster::Raster;
+pub use util::background_mode::BackgroundMode;
+pub use util::position::Position;
 pub use util::color::Color;
+pub use util::geometry::Geometry;
 pub use bar::Bar;
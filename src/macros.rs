@@ -1,11 +1,11 @@
 // Utility macro for setting window properties
-// This returns a Result
+// This returns a Result<()>
 macro_rules! set_prop {
     ($conn:expr, $window:expr, $name:expr, @atom $value:expr) => {
         {
             match xcb::intern_atom($conn, true, $value).get_reply() {
                 Ok(atom) => set_prop!($conn, $window, $name, &[atom.atom()], "ATOM", 32),
-                _ => panic!("Unable to set window property"),
+                Err(e) => Err(Error::InternAtom(XError::new("intern_atom", &e))),
             }
         }
     };
@@ -24,8 +24,9 @@ macro_rules! set_prop {
                     let type_atom = type_atom.atom();
                     let mode = xcb::PROP_MODE_REPLACE as u8;
                     xcb::change_property($conn, mode, $window, property, type_atom, $size, $data);
+                    Ok(())
                 },
-                (Err(_), _) | (_, Err(_)) => panic!("Unable to set window property"),
+                (Err(e), _) | (_, Err(e)) => Err(Error::InternAtom(XError::new("intern_atom", &e))),
             }
         }
     };
@@ -55,14 +56,14 @@ macro_rules! xtry {
         {
             xcb::$func($($args),*)
                 .request_check()
-                .map_err(|e| ErrorKind::XError(e.error_code().to_string()))?;
+                .map_err(|e| XError::new(stringify!($func), &e))?;
         }
     };
     (@render $func:ident, $($args:expr),*) => {
         {
             xcb::render::$func($($args),*)
                 .request_check()
-                .map_err(|e| ErrorKind::XError(e.error_code().to_string()))?;
+                .map_err(|e| XError::new(stringify!($func), &e))?;
         }
     };
 }
@@ -5,9 +5,8 @@ use component::alignment::Alignment;
 use component::picture::Picture;
 use util::geometry::Geometry;
 use component::width::Width;
-use xcb::{self, Rectangle};
 use component::Component;
-use util::color::Color;
+use scheduler::Task;
 use std::sync::Arc;
 use error::*;
 use std::cmp;
@@ -15,180 +14,369 @@ use bar::Bar;
 
 // Renders the state of a component to the bar
 pub fn render(bar: &Bar, component: &mut Component, id: u32) -> Result<()> {
-    // Shorten a few properties for the massive xcb methods
-    let conn = &bar.conn;
-
-    // Get new text and background from component
+    // Get new width, text and background from component
     let width = component.width();
     let background = component.background();
     let foreground = component.foreground();
 
-    // Calculate width and height of element
-    let h = bar.geometry.height;
-    let w = calculate_width(bar, width, &background, &foreground);
+    // Calculate the length required by the content
+    let length = calculate_length(bar, width, &background, &foreground);
 
     {
         // Lock the components
         let mut components = bar.components.lock().unwrap();
-        // Get the X offset of the item
-        let mut x = xoffset_by_id(&components, id, w, bar.geometry.width);
-
-        // Get all components that need to be redrawn
-        components.sort_by(|a, b| a.id.cmp(&b.id));
-        let mut components = components
-            .iter_mut()
-            .filter(|c| (c.id % 3 != 0 || c.id >= id) && c.id % 3 == id % 3)
-            .collect::<Vec<&mut BarComponent>>();
-
-        // Get the index of the current component
-        let comp_index = components.binary_search_by_key(&id, |c| c.id).unwrap_or(0);
-
-        // Check if background or foreground changed
-        let component_changed = {
-            // Closure so &components reference is destructed before updating
-            let new_fg_cache = BarComponentCache::new_fg(&foreground);
-            let new_bg_cache = BarComponentCache::new_bg(&background);
-            let old_fg_cache = &components[comp_index].fg_cache;
-            let old_bg_cache = &components[comp_index].bg_cache;
-            let old_width = components[comp_index].geometry.width;
-            let old_height = components[comp_index].geometry.height;
-            new_bg_cache != *old_bg_cache || new_fg_cache != *old_fg_cache || old_width != w
-                || old_height != h
+
+        // Nothing is rendered if the component has been removed in the meantime
+        let changed = match components.iter_mut().find(|c| c.id == id) {
+            Some(bar_component) => {
+                // Check if background or foreground changed
+                let new_fg_cache = BarComponentCache::new_fg(&foreground);
+                let new_bg_cache = BarComponentCache::new_bg(&background);
+                let changed = new_bg_cache != bar_component.bg_cache
+                    || new_fg_cache != bar_component.fg_cache;
+
+                // Store the new state of the component
+                bar_component.bg_cache = new_bg_cache;
+                bar_component.fg_cache = new_fg_cache;
+                bar_component.background = background;
+                bar_component.foreground = foreground;
+                bar_component.width = width;
+                bar_component.length = length;
+
+                changed
+            }
+            None => return Ok(()),
         };
 
-        // Update the picture if there was a change
-        if component_changed {
-            debug!("Recomposing {}…", id);
-            update_picture(
-                bar,
-                &mut components[comp_index],
-                &background,
-                &foreground,
-                w,
-                h,
-            )?;
-        }
+        // Move all components and redraw the updated one
+        update_layout(bar, &mut components, Some((id, changed)), Vec::new())?;
+    }
 
-        // Clear the difference to old components
-        let width_change = i32::from(components[comp_index].geometry.width) - i32::from(w);
-        if width_change > 0 {
-            clear_old_components(bar, &(*components), x, width_change as i16)?;
-        }
+    // Submit all drawing operations
+    bar.backend.flush();
 
-        // Redraw all selected components
-        for component in components {
-            // Old rectangle for clearing bar
-            let (w, h) = if component.id == id {
-                // Return component dimensions
-                (w, h)
-            } else {
-                (component.geometry.width, component.geometry.height)
-            };
-
-            // Update the component
-            component.set_geometry(Geometry::new(x, 0, w, h));
-
-            // Don't redraw other components if width didn't change
-            // Don't redraw empty components
-            if w > 0 && h > 0 && (width_change != 0 || component.id == id) {
-                // Redraw the component
-                debug!("Redrawing {}…", component.id);
-                component.redraw(bar)?;
-            }
-            x += w as i16;
+    Ok(())
+}
+
+// Move all components to their position on the current bar geometry
+pub fn relayout(bar: &Bar) -> Result<()> {
+    {
+        // Lock the components
+        let mut components = bar.components.lock().unwrap();
+
+        // Clear the whole bar
+        let geometry = bar.geometry();
+        let geometry = Geometry::new(0, 0, geometry.width, geometry.height);
+        update_layout(bar, &mut components, None, vec![geometry])?;
+    }
+
+    // Submit all drawing operations
+    bar.backend.flush();
+
+    Ok(())
+}
+
+// Remove a component and move the remaining components to their new position
+pub fn remove(bar: &Bar, id: u32) -> Result<()> {
+    {
+        // Lock the components
+        let mut components = bar.components.lock().unwrap();
+
+        let index = match components.iter().position(|c| c.id == id) {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        let removed = components.remove(index);
+        if removed.picture != 0 {
+            bar.backend.free_picture(removed.picture);
         }
+
+        // Clear the area previously covered by the component
+        update_layout(bar, &mut components, None, vec![removed.geometry])?;
     }
 
-    // Flush XCB Connection
-    conn.flush();
+    // Submit all drawing operations
+    bar.backend.flush();
 
     Ok(())
 }
 
-// Update the picture of a `BarComponent`
-fn update_picture(
+// Move all components to their position and redraw everything that has changed
+// `updated` is the component which requested a redraw and if its content has changed
+// `cleared` are areas which have to be cleared before redrawing
+fn update_layout(
     bar: &Bar,
-    component: &mut BarComponent,
-    background: &Background,
-    foreground: &Foreground,
-    w: u16,
-    h: u16,
+    components: &mut [BarComponent],
+    updated: Option<(u32, bool)>,
+    mut cleared: Vec<Geometry>,
 ) -> Result<()> {
-    // Don't update the pixmap when it's empty
-    if w == 0 || h == 0 {
+    // Shorten bar properties
+    let (pos, thickness, bar_length) = (bar.position, bar.thickness(), bar.length());
+
+    // Calculate the position of every component
+    components.sort_by_key(BarComponent::sort_key);
+    let layout = layout(components, bar_length);
+
+    // Update the geometry and picture of every component
+    let mut redraw = Vec::with_capacity(components.len());
+    for (component, &(offset, length)) in components.iter_mut().zip(&layout) {
+        let geometry = Geometry::along(pos, offset, length, thickness);
+        let old_geometry = component.geometry;
+        let (is_updated, changed) = match updated {
+            Some((id, changed)) if id == component.id => (true, changed),
+            _ => (false, false),
+        };
+
+        // Clear the old area of moved components
+        let moved = geometry != old_geometry;
+        if moved && !old_geometry.is_empty() {
+            cleared.push(old_geometry);
+        }
+
+        // Let the component know about its new geometry
+        if moved {
+            bar.tasks.send(Task::Geometry(component.id, geometry));
+        }
+
+        // Update the picture if the content or size changed
+        component.set_geometry(geometry);
+        let size = (geometry.width, geometry.height);
+        let resized = size != (old_geometry.width, old_geometry.height);
+        if changed || resized {
+            debug!("Recomposing {}…", component.id);
+            update_picture(bar, component)?;
+        }
+
+        redraw.push(moved || is_updated);
+    }
+
+    // Pictures and geometries are kept up to date while the bar is hidden,
+    // but nothing is drawn until the bar is shown again
+    if bar.is_hidden() {
         return Ok(());
     }
 
-    // Shorten variable names
-    let (conn, gc, win) = (&bar.conn, bar.gcontext, bar.window);
+    // Clear the old areas
+    let background = bar.background();
+    for area in cleared.iter().filter(|area| !area.is_empty()) {
+        bar.composite_picture(background, area.x, area.y, *area)?;
+    }
+
+    // Redraw all moved components and all components which have been cleared
+    for (component, redraw) in components.iter().zip(redraw) {
+        let overlaps = cleared.iter().any(|area| area.intersects(&component.geometry));
+        if (redraw || overlaps) && !component.geometry.is_empty() {
+            debug!("Redrawing {}…", component.id);
+            component.redraw(bar)?;
+        }
+    }
+
+    Ok(())
+}
 
-    // Create pixmap with empty background
-    let pix = conn.generate_id();
-    xtry!(create_pixmap_checked, conn, 32, pix, win, w, h);
-    xtry!(
-        poly_fill_rectangle_checked,
-        conn,
-        pix,
-        gc,
-        &[Rectangle::new(0, 0, w, h)]
-    );
+// Offset and length of every component along the bar
+// Flexible components grow into the space between the alignment groups
+fn layout(components: &[BarComponent], bar_length: u16) -> Vec<(i16, u16)> {
+    let mut lengths = components.iter().map(|c| c.length).collect::<Vec<u16>>();
+    let bar_length = u32::from(bar_length);
 
-    // Free old picture
-    let pict = component.picture;
-    xcb::render::free_picture(conn, pict);
+    // Shrink or hide components if the groups don't fit on the bar
+    let total = lengths.iter().map(|&length| u32::from(length)).sum::<u32>();
+    if total > bar_length {
+        resolve_overflow(components, &mut lengths, total - bar_length);
+    }
 
-    // Create picture from pixmap
-    xtry!(@render create_picture_checked, conn, pict, pix, bar.format32, &[]);
+    // Shift the center group if it would overlap with the left or right group
+    let groups = group_lengths(components, &lengths);
+    let (left, center, right) = (groups[0], groups[1], groups[2]);
+    let centered = (bar_length / 2).saturating_sub(center / 2);
+    let max_start = bar_length.saturating_sub(right + center);
+    let mut center_start = if left <= max_start {
+        cmp::max(cmp::min(centered, max_start), left)
+    } else {
+        centered
+    };
+    if center > 0 && center_start != centered {
+        debug!("Shifting center group from {} to {}", centered, center_start);
+    }
+
+    if !components.iter().any(|c| c.id % 3 == 1) {
+        // Without center group, left and right share the space between them
+        let space = bar_length.saturating_sub(left + right);
+        grow(components, &mut lengths, space, |c| c.id % 3 != 1);
+    } else {
+        // The center group grows in both directions to stay at the same position
+        let left_space = center_start.saturating_sub(left);
+        let right_space = bar_length.saturating_sub(right + center_start + center);
+        let grown = grow(
+            components,
+            &mut lengths,
+            2 * cmp::min(left_space, right_space),
+            |c| c.id % 3 == 1,
+        );
+        center_start -= (grown + 1) / 2;
+
+        // Left and right groups fill the remaining space next to the center group
+        let left_space = left_space - (grown + 1) / 2;
+        let right_space = right_space - grown / 2;
+        grow(components, &mut lengths, left_space, |c| c.id % 3 == 0);
+        grow(components, &mut lengths, right_space, |c| c.id % 3 == 2);
+    }
+
+    // Start of every alignment group
+    let right = group_lengths(components, &lengths)[2];
+    let mut starts = [
+        0,
+        center_start as i16,
+        (i64::from(bar_length) - i64::from(right)) as i16,
+    ];
+
+    // Components are stacked in the order of their sort key
+    components
+        .iter()
+        .zip(lengths)
+        .map(|(component, length)| {
+            let start = &mut starts[(component.id % 3) as usize];
+            let offset = *start;
+            *start += length as i16;
+            (offset, length)
+        })
+        .collect()
+}
+
+// Shrink and hide components until the overflow is resolved
+fn resolve_overflow(components: &[BarComponent], lengths: &mut [u16], overflow: u32) {
+    let mut overflow = overflow;
+
+    // Truncate components which allow it, down to their minimum length
+    let shrinkable = components
+        .iter()
+        .zip(lengths.iter())
+        .map(|(c, &length)| match (c.width.shrink, c.width.fixed) {
+            (true, None) => u32::from(length.saturating_sub(c.width.min)),
+            _ => 0,
+        })
+        .collect::<Vec<u32>>();
+    let total = u64::from(shrinkable.iter().sum::<u32>());
+    let shrink_total = cmp::min(u64::from(overflow), total);
+    for (index, &shrinkable) in shrinkable.iter().enumerate() {
+        if shrinkable == 0 || overflow == 0 {
+            continue;
+        }
+
+        // Every component is shrunk relative to how much it can be shrunk
+        let shrink = (shrink_total * u64::from(shrinkable) + total - 1) / total;
+        let shrink = cmp::min(cmp::min(shrink as u32, shrinkable), overflow);
+        lengths[index] -= shrink as u16;
+        overflow -= shrink;
+        debug!("Truncating component {} to {}", components[index].id, lengths[index]);
+    }
+
+    // Hide components by priority, starting with the lowest priority and the newest component
+    let mut hideable = (0..components.len())
+        .filter(|&i| components[i].width.priority.is_some() && lengths[i] > 0)
+        .collect::<Vec<usize>>();
+    hideable.sort_by_key(|&i| (components[i].width.priority, cmp::Reverse(components[i].id)));
+    for index in hideable {
+        if overflow == 0 {
+            break;
+        }
+        debug!("Hiding component {} to avoid overlap", components[index].id);
+        overflow = overflow.saturating_sub(u32::from(lengths[index]));
+        lengths[index] = 0;
+    }
+
+    if overflow > 0 {
+        debug!("Components still overlap by {}", overflow);
+    }
+}
+
+// Share space between flexible components based on their weight
+// Returns the space used by the components
+fn grow<F>(components: &[BarComponent], lengths: &mut [u16], space: u32, filter: F) -> u32
+where
+    F: Fn(&BarComponent) -> bool,
+{
+    let mut flexible = components
+        .iter()
+        .enumerate()
+        .filter(|&(_, c)| filter(c) && c.width.fixed.is_none() && c.width.flex > 0)
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
+
+    // Components which reached their maximum length pass their share on to the others
+    let mut remaining = space;
+    while remaining > 0 && !flexible.is_empty() {
+        let weights = flexible
+            .iter()
+            .map(|&i| u32::from(components[i].width.flex))
+            .sum::<u32>();
+
+        let mut used = 0;
+        flexible.retain(|&i| {
+            let flex = u64::from(components[i].width.flex);
+            let share = (u64::from(remaining) * flex / u64::from(weights)) as u32;
+            let max = u32::from(components[i].width.max);
+            let length = cmp::min(u32::from(lengths[i]) + share, max);
+            used += length - u32::from(lengths[i]);
+            lengths[i] = length as u16;
+            length < max
+        });
+
+        // Stop when the rest can't be shared without fractions of pixels
+        if used == 0 {
+            break;
+        }
+        remaining -= used;
+    }
+
+    space - remaining
+}
+
+// Summed up length of every alignment group
+fn group_lengths(components: &[BarComponent], lengths: &[u16]) -> [u32; 3] {
+    let mut group_lengths = [0; 3];
+    for (component, &length) in components.iter().zip(lengths) {
+        group_lengths[(component.id % 3) as usize] += u32::from(length);
+    }
+    group_lengths
+}
+
+// Update the picture of a `BarComponent` to its current size and content
+fn update_picture(bar: &Bar, component: &mut BarComponent) -> Result<()> {
+    // Don't update the picture when it's empty
+    let (w, h) = (component.geometry.width, component.geometry.height);
+    if w == 0 || h == 0 {
+        return Ok(());
+    }
+
+    // Create picture with empty background
+    let pict = bar.backend.create_picture(w, h)?;
+
+    // Free old picture
+    if component.picture != 0 {
+        bar.backend.free_picture(component.picture);
+    }
+    component.picture = pict;
 
     // Render the background color
+    let background = &component.background;
     if let Some(color) = background.color {
-        render_color(bar, pix, w, h, color)?;
+        bar.backend.fill(pict, color, Geometry::new(0, 0, w, h))?;
     }
 
     // Render the background image if it's not `None`
     for image in &background.images {
-        render_picture(bar, pict, w, &image.arc, image.alignment, 0)?;
+        render_picture(bar, pict, w, h, &image.arc, image.alignment, 0)?;
     }
 
     // Render the foreground text
+    let foreground = &component.foreground;
     if let Some(ref text) = foreground.text {
         let yoffset = foreground.yoffset.unwrap_or(bar.text_yoffset);
-        render_picture(bar, pict, w, &text.arc, foreground.alignment, yoffset)?;
+        render_picture(bar, pict, w, h, &text.arc, foreground.alignment, yoffset)?;
     }
 
-    // Free pixmap
-    xcb::free_pixmap(conn, pix);
-
-    Ok(())
-}
-
-// Render the a color to a pixmap
-fn render_color(bar: &Bar, pix: u32, w: u16, h: u16, color: Color) -> Result<()> {
-    // Shorten bar variable names
-    let conn = &bar.conn;
-
-    // Create a GC with the color
-    let col_gc = conn.generate_id();
-    xtry!(
-        create_gc_checked,
-        conn,
-        col_gc,
-        pix,
-        &[(xcb::ffi::xproto::XCB_GC_FOREGROUND, color.into())]
-    );
-
-    // Fill the pixmap with the GC color
-    xtry!(
-        poly_fill_rectangle_checked,
-        conn,
-        pix,
-        col_gc,
-        &[Rectangle::new(0, 0, w, h)]
-    );
-
-    // Free gc after filling the rectangle
-    xcb::free_gc(conn, col_gc);
-
     Ok(())
 }
 
@@ -197,127 +385,169 @@ fn render_picture(
     bar: &Bar,
     tar_pict: u32,
     w: u16,
+    h: u16,
     src_pict: &Arc<Picture>,
     alignment: Alignment,
     yoff: i16,
 ) -> Result<()> {
-    // Shorten bar variable names
-    let conn = &bar.conn;
-
     // Get width and height of the picture
     let pw = src_pict.geometry.width;
     let ph = src_pict.geometry.height;
 
-    // Get X position
-    let x = alignment.x_offset(w, pw);
-
-    // Put image on pixmap
-    let op = xcb::render::PICT_OP_OVER as u8;
-    let src_xid = src_pict.xid;
-    xtry!(@render composite_checked, conn, op, src_xid, 0, tar_pict, 0, 0, 0, 0, x, yoff, pw, ph);
-
-    Ok(())
-}
-
-// Component's X-Offset by id
-// If id is from center component, will return new X of the first component
-fn xoffset_by_id(components: &[BarComponent], id: u32, new_width: u16, bar_width: u16) -> i16 {
-    // Check if component is not left-aligned
-    if id % 3 != 0 {
-        // Filter unrelevant components
-        let components = components
-            .iter()
-            .filter(|c| c.id != id && c.id % 3 == id % 3);
-
-        // Get new width of all components
-        let mut width = f64::from(components.map(|c| c.geometry.width).sum::<u16>());
-        width += f64::from(new_width);
-
-        if id % 3 == 1 {
-            // Center
-            (f64::from(bar_width) / 2f64 - width / 2f64) as i16
-        } else {
-            // Right
-            bar_width as i16 - width as i16
-        }
+    // Get position, pictures are centered horizontally on vertical bars
+    let (x, y) = if bar.position.is_vertical() {
+        (Alignment::CENTER.offset(w, pw), alignment.offset(h, ph) + yoff)
     } else {
-        // Return selected component's old X
-        components
-            .iter()
-            .filter(|c| id > c.id && c.id % 3 == id % 3)
-            .map(|c| c.geometry.width)
-            .sum::<u16>() as i16
-    }
-}
+        (alignment.offset(w, pw), yoff)
+    };
 
-// Clear the old area before redrawing
-fn clear_old_components(
-    bar: &Bar,
-    components: &[&mut BarComponent],
-    new_start: i16,
-    width_change: i16,
-) -> Result<()> {
-    // Bar shorthands
-    let bar_height = bar.geometry.height;
-
-    // Get old start x
-    let old_width_all = components.iter().map(|c| c.geometry.width).sum::<u16>() as i16;
-    let old_start = components[0].geometry.x;
-
-    // Redraw from old_x to new_x
-    if old_start < new_start {
-        let width = (new_start - old_start) as u16;
-        bar.composite_picture(bar.background, old_start, old_start, width, bar_height)?;
-    }
-
-    // Get the old end x and new end x
-    let old_end = old_start + old_width_all;
-    let new_end = old_end - width_change;
-
-    if old_end > new_end {
-        let width = (old_end - new_end) as u16;
-        bar.composite_picture(bar.background, new_end, new_end, width, bar.geometry.height)?;
-    }
-
-    Ok(())
+    // Put image on picture
+    let area = Geometry::new(x, y, pw, ph);
+    bar.backend.composite(src_pict.xid, 0, 0, tar_pict, area)
 }
 
-// Calculate the width of a component
-fn calculate_width(
+// Calculate the length of a component along the bar
+// On vertical bars the `Width` restrictions are applied to the height
+fn calculate_length(
     bar: &Bar,
     width: Width,
     background: &Background,
     foreground: &Foreground,
 ) -> u16 {
+    // Shorten bar variable names
+    let (pos, bar_length) = (bar.position, bar.length());
+
     // Just return fixed if it's some
     if let Some(fixed) = width.fixed {
-        return cmp::min(fixed, bar.geometry.width);
+        return cmp::min(fixed, bar_length);
     }
 
     // Start with min which defaults to 0
     let mut w = width.min;
 
-    // Set the width to the biggest background image
+    // Set the length to the biggest background image
     for image in &background.images {
-        // Check if bg width should be ignored
+        // Check if bg length should be ignored
         if !width.ignore_background {
-            w = cmp::max(w, image.arc.geometry.width);
+            w = cmp::max(w, image.arc.geometry.length(pos));
         }
     }
 
-    // Set to text width if it isn't smaller than min
+    // Set to text length if it isn't smaller than min
     if let Some(ref text) = foreground.text {
-        // Check if text width should be ignored
+        // Check if text length should be ignored
         if !width.ignore_foreground {
-            w = cmp::max(w, text.arc.geometry.width);
+            w = cmp::max(w, text.arc.geometry.length(pos));
         }
     }
 
     // Make sure it's not bigger than the whole bar
-    w = cmp::min(w, bar.geometry.width);
+    w = cmp::min(w, bar_length);
 
     // Make sure it's not bigger than max
     w = cmp::min(w, width.max);
 
     w
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The alignment group is taken from the id, 0 is left, 1 is center and 2 is right
+    fn component(id: u32, length: u16, width: Width) -> BarComponent {
+        let mut component = BarComponent::new(id, 0);
+        component.length = length;
+        component.width = width;
+        component
+    }
+
+    #[test]
+    fn flex_shares_space_by_weight() {
+        let components = [
+            component(0, 10, Width::new().flex(1)),
+            component(3, 10, Width::new().flex(3)),
+            component(2, 20, Width::new()),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 25), (25, 55), (80, 20)]);
+    }
+
+    #[test]
+    fn flex_respects_max() {
+        let components = [
+            component(0, 10, Width::new().flex(1).max(20)),
+            component(3, 10, Width::new().flex(1)),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 20), (20, 80)]);
+    }
+
+    #[test]
+    fn flex_ignores_fixed() {
+        let components = [
+            component(0, 10, Width::new().flex(1).fixed(10)),
+            component(2, 10, Width::new()),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 10), (90, 10)]);
+    }
+
+    #[test]
+    fn flex_center_stays_centered() {
+        let components = [
+            component(0, 30, Width::new()),
+            component(1, 20, Width::new().flex(1)),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 30), (30, 40)]);
+    }
+
+    #[test]
+    fn overflow_shrinks_by_shrinkable_length() {
+        let components = [
+            component(0, 60, Width::new().shrink()),
+            component(3, 30, Width::new().shrink()),
+            component(2, 40, Width::new()),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 40), (40, 20), (60, 40)]);
+    }
+
+    #[test]
+    fn overflow_shrinks_down_to_min() {
+        let components = [
+            component(0, 80, Width::new().shrink().min(70)),
+            component(2, 50, Width::new()),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 70), (50, 50)]);
+    }
+
+    #[test]
+    fn overflow_hides_by_priority() {
+        let components = [
+            component(0, 60, Width::new().priority(1)),
+            component(3, 30, Width::new().priority(0)),
+            component(2, 40, Width::new()),
+            component(5, 20, Width::new().priority(0)),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 60), (60, 0), (60, 40), (100, 0)]);
+    }
+
+    #[test]
+    fn overflow_shrinks_before_hiding() {
+        let components = [
+            component(0, 60, Width::new().shrink().min(50)),
+            component(3, 30, Width::new().priority(0)),
+            component(2, 40, Width::new()),
+        ];
+        assert_eq!(layout(&components, 100), [(0, 50), (50, 0), (60, 40)]);
+    }
+
+    #[test]
+    fn center_shifts_away_from_left() {
+        let components = [component(0, 50, Width::new()), component(1, 20, Width::new())];
+        assert_eq!(layout(&components, 100), [(0, 50), (50, 20)]);
+    }
+
+    #[test]
+    fn center_shifts_away_from_right() {
+        let components = [component(1, 20, Width::new()), component(2, 50, Width::new())];
+        assert_eq!(layout(&components, 100), [(30, 20), (50, 50)]);
+    }
+}
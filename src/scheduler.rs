@@ -0,0 +1,477 @@
+use chan::{Receiver, Select};
+use component::Component;
+use component::info::ComponentInfo;
+use std::thread::{self, JoinHandle};
+use std::any::Any;
+use event::Event;
+use bar::Bar;
+use util::geometry::Geometry;
+use render;
+use timer;
+#[cfg(feature = "futures")]
+use component::async_component::RedrawStream;
+#[cfg(feature = "futures")]
+use futures::executor::{self, Notify, NotifyHandle, Spawn};
+#[cfg(feature = "futures")]
+use futures::Async;
+#[cfg(feature = "futures")]
+use chan::Sender;
+#[cfg(feature = "futures")]
+use std::sync::Arc;
+
+// Work for the scheduler thread
+// Everything except adding components and `Stop` is addressed to the component with the id
+pub enum Task {
+    Add(Box<ComponentInfo>, Box<Component + Send>),
+    Remove(u32),
+    Redraw(u32),
+    Event(u32, Event),
+    Message(u32, Box<Any + Send>),
+    Geometry(u32, Geometry),
+    #[cfg(feature = "futures")]
+    AddAsync(Box<ComponentInfo>, Box<Component + Send>, RedrawStream),
+    // The stream of the component has been notified
+    #[cfg(feature = "futures")]
+    Poll(u32),
+    Stop,
+}
+
+// Component managed by the scheduler
+struct Entry {
+    id: u32,
+    component: Box<Component + Send>,
+    // `None` after the component dropped the sender of its timer
+    timer: Option<Receiver<()>>,
+    // `None` after the stream has ended
+    #[cfg(feature = "futures")]
+    stream: Option<Spawn<RedrawStream>>,
+}
+
+impl Entry {
+    fn new(id: u32, mut component: Box<Component + Send>) -> Self {
+        let timer = Some(component.redraw_timer());
+        Entry {
+            id,
+            component,
+            timer,
+            #[cfg(feature = "futures")]
+            stream: None,
+        }
+    }
+
+    // Async components are redrawn by their stream, so they don't have a timer
+    #[cfg(feature = "futures")]
+    fn new_async(id: u32, component: Box<Component + Send>, stream: RedrawStream) -> Self {
+        Entry {
+            id,
+            component,
+            timer: None,
+            stream: Some(executor::spawn(stream)),
+        }
+    }
+}
+
+// Wakes up the scheduler when a stream is ready to be polled
+#[cfg(feature = "futures")]
+struct Waker(Sender<Task>);
+
+#[cfg(feature = "futures")]
+impl Notify for Waker {
+    fn notify(&self, id: usize) {
+        self.0.send(Task::Poll(id as u32));
+    }
+}
+
+// The next thing the scheduler has to take care of
+enum Ready {
+    Task(Option<Task>),
+    Timer(usize, Option<()>),
+}
+
+// Start the thread which runs all components of a bar
+pub fn spawn(bar: Bar, tasks: Receiver<Task>) -> JoinHandle<()> {
+    thread::spawn(move || run(&bar, &tasks))
+}
+
+// Handle timers, events and requests of all components until the bar is stopped
+fn run(bar: &Bar, tasks: &Receiver<Task>) {
+    let mut entries: Vec<Entry> = Vec::new();
+    #[cfg(feature = "futures")]
+    let waker = NotifyHandle::from(Arc::new(Waker(bar.tasks.clone())));
+
+    loop {
+        let ready = match wait(tasks, &entries) {
+            Some(ready) => ready,
+            None => {
+                error!("Scheduler selected unknown channel");
+                continue;
+            }
+        };
+
+        match ready {
+            Ready::Task(Some(Task::Add(info, mut component))) => {
+                // Requests sent by the hook are queued after the component has been added
+                let id = info.handle.id;
+                component.on_added(bar, *info);
+                entries.push(Entry::new(id, component));
+
+                // Every component is drawn once after being added
+                if let Some(entry) = entries.last_mut() {
+                    redraw(bar, entry);
+                }
+            }
+            #[cfg(feature = "futures")]
+            Ready::Task(Some(Task::AddAsync(info, mut component, stream))) => {
+                let id = info.handle.id;
+                component.on_added(bar, *info);
+                entries.push(Entry::new_async(id, component, stream));
+
+                // Draw once, then poll the stream to get notified when it's ready
+                if let Some(entry) = entries.last_mut() {
+                    redraw(bar, entry);
+                    poll(bar, entry, &waker);
+                }
+            }
+            #[cfg(feature = "futures")]
+            Ready::Task(Some(Task::Poll(id))) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    poll(bar, entry, &waker);
+                }
+            }
+            Ready::Task(Some(Task::Remove(id))) => {
+                debug!("Component {} removed.", id);
+                if let Some(index) = entries.iter().position(|entry| entry.id == id) {
+                    let mut entry = entries.remove(index);
+                    entry.component.on_removed();
+                    release_timer(&entries, entry);
+                }
+            }
+            Ready::Task(Some(Task::Redraw(id))) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    debug!("Component {} redraw requested by handle.", id);
+                    redraw(bar, entry);
+                }
+            }
+            Ready::Task(Some(Task::Event(id, event))) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    debug!("Component {} received event.", id);
+                    if entry.component.event(event) {
+                        debug!("Component {} requested redraw after event.", id);
+                        redraw(bar, entry);
+                    }
+                }
+            }
+            Ready::Task(Some(Task::Message(id, message))) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    debug!("Component {} received message.", id);
+                    if entry.component.message(message) {
+                        debug!("Component {} requested redraw after message.", id);
+                        redraw(bar, entry);
+                    }
+                }
+            }
+            Ready::Task(Some(Task::Geometry(id, geometry))) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    debug!("Component {} geometry changed to {:?}.", id, geometry);
+                    if entry.component.on_geometry_changed(geometry) && !bar.is_quitting() {
+                        // The hook already decided to redraw, so `update` is not asked again
+                        debug!("Component {} requested redraw after geometry change.", id);
+                        let res = render::render(bar, &mut *entry.component, id);
+                        err!(res, "Component {}", id);
+                    }
+                }
+            }
+            Ready::Task(Some(Task::Stop)) | Ready::Task(None) => {
+                debug!("Scheduler stopped.");
+                while let Some(mut entry) = entries.pop() {
+                    entry.component.on_removed();
+                    release_timer(&entries, entry);
+                }
+                return;
+            }
+            Ready::Timer(index, tick) => {
+                // The tick is for every component sharing the timer
+                let timer = entries[index].timer.clone();
+                for entry in entries.iter_mut().filter(|entry| entry.timer == timer) {
+                    if tick.is_some() {
+                        debug!("Component {} requested redraw without event.", entry.id);
+                        redraw(bar, entry);
+                    } else {
+                        // Keep receiving events and requests without redrawing periodically
+                        debug!("Component {} timer disconnected.", entry.id);
+                        entry.timer = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Block until a task has been received or one of the timers fired
+// Returns `None` if the selected channel is neither the task channel nor a timer
+fn wait(tasks: &Receiver<Task>, entries: &[Entry]) -> Option<Ready> {
+    let mut select = Select::new();
+    let task = select.recv(tasks);
+    let timers = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| entry.timer.as_ref().map(|timer| (index, timer)))
+        .map(|(index, timer)| (index, select.recv(timer)))
+        .collect::<Vec<_>>();
+
+    let id = select.select();
+    if id == task.id() {
+        return Some(Ready::Task(task.into_value()));
+    }
+
+    // Components can share a timer, only the last one is registered with the select
+    // The scheduler passes the tick on to all components with the same timer
+    timers
+        .into_iter()
+        .rev()
+        .find(|&(_, ref timer)| timer.id() == id)
+        .map(|(index, timer)| Ready::Timer(index, timer.into_value()))
+}
+
+// Stop the timer of a removed entry, unless it's shared with one of the remaining entries
+fn release_timer(entries: &[Entry], entry: Entry) {
+    if let Some(timer) = entry.timer {
+        if !entries.iter().any(|other| other.timer.as_ref() == Some(&timer)) {
+            timer::release(&timer);
+        }
+    }
+}
+
+// Take all items which are ready from the stream of the component
+// The component is redrawn once if the stream returned at least one item
+#[cfg(feature = "futures")]
+fn poll(bar: &Bar, entry: &mut Entry, waker: &NotifyHandle) {
+    let mut ready = false;
+    loop {
+        let res = match entry.stream {
+            Some(ref mut stream) => stream.poll_stream_notify(waker, entry.id as usize),
+            None => break,
+        };
+
+        match res {
+            Ok(Async::Ready(Some(()))) => ready = true,
+            Ok(Async::NotReady) => break,
+            Ok(Async::Ready(None)) | Err(()) => {
+                // Keep receiving events and requests without redrawing from the stream
+                debug!("Component {} stream ended.", entry.id);
+                entry.stream = None;
+                break;
+            }
+        }
+    }
+
+    if ready {
+        debug!("Component {} requested redraw from stream.", entry.id);
+        redraw(bar, entry);
+    }
+}
+
+// Update the component and render it if requested
+fn redraw(bar: &Bar, entry: &mut Entry) {
+    if entry.component.update() && !bar.is_quitting() {
+        let res = render::render(bar, &mut *entry.component, entry.id);
+        err!(res, "Component {}", entry.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chan::{self, Receiver, Sender};
+    use std::any::Any;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use util::geometry::Geometry;
+    use {Alignment, Background, Bar, BarBuilder, Component, ComponentHandle, ComponentInfo, Raster,
+         Width};
+
+    // Reports when it has been dropped by the scheduler
+    struct DroppedComponent(Sender<()>);
+
+    impl Component for DroppedComponent {}
+
+    impl Drop for DroppedComponent {
+        fn drop(&mut self) {
+            self.0.send(());
+        }
+    }
+
+    // Messages itself as soon as it has been added
+    struct AddedComponent(Option<Sender<()>>);
+
+    impl Component for AddedComponent {
+        fn on_added(&mut self, _bar: &Bar, info: ComponentInfo) {
+            if let Some(reply) = self.0.take() {
+                info.handle.send(reply);
+            }
+        }
+
+        fn message(&mut self, message: Box<Any + Send>) -> bool {
+            if let Ok(reply) = message.downcast::<Sender<()>>() {
+                reply.send(());
+            }
+            false
+        }
+    }
+
+    // Only updated once, but redrawn after every geometry change
+    struct GeometryComponent {
+        calls: Sender<&'static str>,
+        updated: bool,
+    }
+
+    impl Component for GeometryComponent {
+        fn update(&mut self) -> bool {
+            let first = !self.updated;
+            self.updated = true;
+            first
+        }
+
+        fn background(&self) -> Background {
+            self.calls.send("render");
+            Background::new()
+        }
+
+        fn width(&self) -> Width {
+            Width::new().flex(1)
+        }
+
+        fn on_geometry_changed(&mut self, _geometry: Geometry) -> bool {
+            self.calls.send("geometry");
+            true
+        }
+
+        // Reply to synchronize with the scheduler
+        fn message(&mut self, message: Box<Any + Send>) -> bool {
+            if let Ok(reply) = message.downcast::<Sender<()>>() {
+                reply.send(());
+            }
+            false
+        }
+    }
+
+    // Counts how often it has been updated
+    struct TimerComponent {
+        timer: Receiver<()>,
+        updates: Arc<AtomicUsize>,
+    }
+
+    impl Component for TimerComponent {
+        fn redraw_timer(&mut self) -> Receiver<()> {
+            self.timer.clone()
+        }
+
+        fn update(&mut self) -> bool {
+            self.updates.fetch_add(1, Ordering::SeqCst);
+            false
+        }
+
+        // Reply to synchronize with the scheduler
+        fn message(&mut self, message: Box<Any + Send>) -> bool {
+            if let Ok(reply) = message.downcast::<Sender<()>>() {
+                reply.send(());
+            }
+            false
+        }
+    }
+
+    // Takes away space from flexible components
+    struct FixedComponent;
+
+    impl Component for FixedComponent {
+        fn alignment(&self) -> Alignment {
+            Alignment::RIGHT
+        }
+
+        fn width(&self) -> Width {
+            Width::new().fixed(30)
+        }
+    }
+
+    fn bar(raster: &Raster) -> Bar {
+        BarBuilder::new().height(20).spawn_headless(raster).unwrap()
+    }
+
+    // Wait until the scheduler has processed everything sent before
+    fn sync(handle: &ComponentHandle) {
+        let (tx, rx) = chan::sync::<()>(1);
+        handle.send(tx);
+        rx.recv();
+    }
+
+    #[test]
+    fn dropping_bar_stops_scheduler() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let (tx, rx) = chan::sync(1);
+        let handle = bar.add(DroppedComponent(tx));
+
+        drop(bar);
+        assert_eq!(rx.recv(), Some(()));
+
+        // Nothing is sent to the stopped scheduler anymore
+        handle.request_redraw();
+    }
+
+    #[test]
+    fn message_from_added_hook_is_received() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let (tx, rx) = chan::sync(1);
+        bar.add(AddedComponent(Some(tx)));
+
+        assert_eq!(rx.recv(), Some(()));
+    }
+
+    #[test]
+    fn geometry_change_redraws_without_update() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let (calls, calls_rx) = chan::async();
+        let handle = bar.add(GeometryComponent {
+            calls,
+            updated: false,
+        });
+        sync(&handle);
+
+        // Shrinks the flexible component
+        bar.add(FixedComponent);
+        sync(&handle);
+        sync(&handle);
+
+        let mut last = Vec::new();
+        loop {
+            chan_select! {
+                default => break,
+                calls_rx.recv() -> call => last.push(call.unwrap()),
+            }
+        }
+        assert_eq!(&last[last.len() - 2..], ["geometry", "render"]);
+    }
+
+    #[test]
+    fn shared_timer_redraws_every_component() {
+        let raster = Raster::new(200, 100);
+        let mut bar = bar(&raster);
+        let (tick, timer) = chan::sync(0);
+        let updates = Arc::new(AtomicUsize::new(0));
+        bar.add(TimerComponent {
+            timer: timer.clone(),
+            updates: updates.clone(),
+        });
+        let handle = bar.add(TimerComponent {
+            timer,
+            updates: updates.clone(),
+        });
+        sync(&handle);
+        assert_eq!(updates.load(Ordering::SeqCst), 2);
+
+        tick.send(());
+        sync(&handle);
+        assert_eq!(updates.load(Ordering::SeqCst), 4);
+    }
+}
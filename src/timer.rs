@@ -0,0 +1,341 @@
+//! Timers for periodically redrawing components.
+//!
+//! All timers are driven by a single thread shared between every bar of the process, so using
+//! them in [`Component::redraw_timer`] does not require a separate thread for every component.
+//!
+//! If a component is busy while its timer fires, the tick is kept until the component is ready
+//! again. Multiple missed ticks are merged into one, so a component is never redrawn in bursts.
+//!
+//! [`Component::redraw_timer`]: ../trait.Component.html#method.redraw_timer
+use chan::{self, Receiver, Sender};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::thread;
+use std::cmp;
+
+// Upper limit for sleeping while a wall-clock timer is waiting
+// This makes sure changes of the system time and resuming from suspend are noticed quickly
+const WALL_CLOCK_RESOLUTION_MS: u64 = 1000;
+
+// Shortest interval accepted for any timer
+const MIN_INTERVAL_MS: u64 = 1;
+
+lazy_static! {
+    static ref TIMERS: Timers = Timers::start();
+}
+
+// State shared with the timer thread
+struct Timers {
+    timers: Mutex<Vec<Timer>>,
+    changed: Condvar,
+}
+
+struct Timer {
+    interval: Duration,
+    deadline: Deadline,
+    tx: Sender<()>,
+    // Identifies the timer when it's released, without keeping a clone of its receiver
+    id: u64,
+}
+
+#[derive(Debug, PartialEq)]
+enum Deadline {
+    // Monotonic clock, does not advance while the system is suspended
+    Interval(Instant),
+    // Boundary of the system time, relative to the unix epoch
+    Aligned(SystemTime),
+}
+
+/// Create a timer which fires every time the interval has elapsed.
+///
+/// The first tick is sent after one interval. The timer is based on a monotonic clock, so it is
+/// paused while the system is suspended and is not affected by changes of the system time.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate leechbar;
+/// extern crate chan;
+/// use leechbar::{timer, Component};
+/// use std::time::Duration;
+///
+/// struct MyComponent;
+/// impl Component for MyComponent {
+///     // Redraw this component every 5 seconds
+///     fn redraw_timer(&mut self) -> chan::Receiver<()> {
+///         timer::every(Duration::from_secs(5))
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub fn every(interval: Duration) -> Receiver<()> {
+    let interval = cmp::max(interval, Duration::from_millis(MIN_INTERVAL_MS));
+    let deadline = Deadline::Interval(Instant::now() + interval);
+    TIMERS.add(interval, deadline)
+}
+
+/// Create a timer which fires on every multiple of the interval on the wall clock.
+///
+/// Using one minute as interval, the timer fires at the start of every minute. This is useful for
+/// things like clocks, which should update as soon as the time has changed. Boundaries are
+/// calculated relative to the unix epoch, so intervals which do not evenly divide a day might not
+/// line up with the local time.
+///
+/// Boundaries which were missed while the system was suspended are merged into a single tick,
+/// which is sent right after resuming.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate leechbar;
+/// extern crate chan;
+/// use leechbar::{timer, Component};
+/// use std::time::Duration;
+///
+/// struct MyComponent;
+/// impl Component for MyComponent {
+///     // Redraw this component at the start of every minute
+///     fn redraw_timer(&mut self) -> chan::Receiver<()> {
+///         timer::aligned(Duration::from_secs(60))
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub fn aligned(interval: Duration) -> Receiver<()> {
+    let interval = cmp::max(interval, Duration::from_millis(MIN_INTERVAL_MS));
+    let deadline = Deadline::Aligned(next_boundary(SystemTime::now(), interval));
+    TIMERS.add(interval, deadline)
+}
+
+// Stop the timer which belongs to this receiver
+// Receivers which have not been created by this module are ignored
+pub(crate) fn release(rx: &Receiver<()>) {
+    TIMERS.remove(rx);
+}
+
+impl Timers {
+    // Spawn the thread which sends out all ticks
+    fn start() -> Self {
+        thread::spawn(|| TIMERS.run());
+        Timers {
+            timers: Mutex::new(Vec::new()),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn add(&self, interval: Duration, deadline: Deadline) -> Receiver<()> {
+        // Buffer a single tick, so ticks aren't lost while the receiver is busy
+        let (tx, rx) = chan::sync(1);
+        let timer = Timer {
+            interval,
+            deadline,
+            tx,
+            id: channel_id(&rx),
+        };
+
+        self.timers.lock().unwrap().push(timer);
+        self.changed.notify_one();
+
+        rx
+    }
+
+    fn remove(&self, rx: &Receiver<()>) {
+        let id = channel_id(rx);
+        let mut timers = self.timers.lock().unwrap();
+        let len = timers.len();
+        timers.retain(|timer| timer.id != id);
+        if timers.len() != len {
+            debug!("Timer released.");
+        }
+    }
+
+    // Send ticks until the process exits
+    fn run(&self) {
+        let mut timers = self.timers.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let wall = SystemTime::now();
+
+            for timer in timers.iter_mut() {
+                timer.update(now, wall);
+            }
+
+            // Sleep until the next timer is due or a new timer has been added
+            let timeout = timers.iter().map(|timer| timer.remaining(now, wall)).min();
+            timers = match timeout {
+                Some(timeout) => self.changed.wait_timeout(timers, timeout).unwrap().0,
+                None => self.changed.wait(timers).unwrap(),
+            };
+        }
+    }
+}
+
+impl Timer {
+    // Send a tick if the timer is due and schedule the next one
+    fn update(&mut self, now: Instant, wall: SystemTime) {
+        if self.is_due(now, wall) {
+            tick(&self.tx);
+            self.reschedule(now, wall);
+        } else if self.until_deadline(now, wall) > self.interval {
+            // The system time has been set back, start over at the next boundary
+            self.reschedule(now, wall);
+        }
+    }
+
+    fn is_due(&self, now: Instant, wall: SystemTime) -> bool {
+        match self.deadline {
+            Deadline::Interval(deadline) => now >= deadline,
+            Deadline::Aligned(deadline) => wall >= deadline,
+        }
+    }
+
+    // Time until the timer is due
+    fn until_deadline(&self, now: Instant, wall: SystemTime) -> Duration {
+        match self.deadline {
+            Deadline::Interval(deadline) if deadline > now => deadline - now,
+            Deadline::Interval(_) => Duration::from_secs(0),
+            Deadline::Aligned(deadline) => deadline.duration_since(wall).unwrap_or_default(),
+        }
+    }
+
+    // Time to sleep before checking the timer again
+    // For wall-clock timers this is limited to the resolution of the wall clock
+    fn remaining(&self, now: Instant, wall: SystemTime) -> Duration {
+        let remaining = self.until_deadline(now, wall);
+        match self.deadline {
+            Deadline::Interval(_) => remaining,
+            Deadline::Aligned(_) => {
+                cmp::min(remaining, Duration::from_millis(WALL_CLOCK_RESOLUTION_MS))
+            }
+        }
+    }
+
+    // Schedule the next tick, missed ticks are skipped
+    fn reschedule(&mut self, now: Instant, wall: SystemTime) {
+        self.deadline = match self.deadline {
+            Deadline::Interval(_) => Deadline::Interval(now + self.interval),
+            Deadline::Aligned(_) => Deadline::Aligned(next_boundary(wall, self.interval)),
+        };
+    }
+}
+
+// Send a tick without blocking
+// If the last tick has not been received yet, the new one is dropped
+fn tick(tx: &Sender<()>) {
+    chan_select! {
+        default => (),
+        tx.send(()) => (),
+    }
+}
+
+// First multiple of the interval since the unix epoch after `time`
+fn next_boundary(time: SystemTime, interval: Duration) -> SystemTime {
+    let elapsed = nanos(time.duration_since(UNIX_EPOCH).unwrap_or_default());
+    let interval = nanos(interval);
+    let next = (elapsed / interval + 1) * interval;
+    UNIX_EPOCH + Duration::new(next / 1_000_000_000, (next % 1_000_000_000) as u32)
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
+
+// Receivers of the same channel have the same id
+fn channel_id(rx: &Receiver<()>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    rx.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn aligned_timer(interval: Duration, deadline: SystemTime) -> (Timer, Receiver<()>) {
+        let (tx, rx) = chan::sync(1);
+        let timer = Timer {
+            interval,
+            deadline: Deadline::Aligned(deadline),
+            tx,
+            id: channel_id(&rx),
+        };
+        (timer, rx)
+    }
+
+    // Receive a tick without blocking
+    fn try_recv(rx: &Receiver<()>) -> bool {
+        let mut received = false;
+        chan_select! {
+            default => (),
+            rx.recv() => received = true,
+        }
+        received
+    }
+
+    #[test]
+    fn next_boundary_rounds_up() {
+        let time = UNIX_EPOCH + secs(125);
+        assert_eq!(next_boundary(time, secs(60)), UNIX_EPOCH + secs(180));
+    }
+
+    #[test]
+    fn next_boundary_skips_current_boundary() {
+        let time = UNIX_EPOCH + secs(120);
+        assert_eq!(next_boundary(time, secs(60)), UNIX_EPOCH + secs(180));
+    }
+
+    #[test]
+    fn next_boundary_below_one_second() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_500);
+        let interval = Duration::from_millis(250);
+        assert_eq!(next_boundary(time, interval), UNIX_EPOCH + Duration::from_millis(1_750));
+    }
+
+    #[test]
+    fn due_timer_ticks() {
+        let wall = UNIX_EPOCH + secs(120);
+        let (mut timer, rx) = aligned_timer(secs(60), wall);
+
+        timer.update(Instant::now(), wall);
+        assert!(try_recv(&rx));
+        assert_eq!(timer.deadline, Deadline::Aligned(UNIX_EPOCH + secs(180)));
+    }
+
+    #[test]
+    fn pending_timer_waits() {
+        let wall = UNIX_EPOCH + secs(150);
+        let (mut timer, rx) = aligned_timer(secs(60), UNIX_EPOCH + secs(180));
+
+        timer.update(Instant::now(), wall);
+        assert!(!try_recv(&rx));
+        assert_eq!(timer.deadline, Deadline::Aligned(UNIX_EPOCH + secs(180)));
+    }
+
+    #[test]
+    fn set_back_clock_reschedules() {
+        // The clock has been set back by an hour after the timer was scheduled
+        let wall = UNIX_EPOCH + secs(3_630);
+        let (mut timer, rx) = aligned_timer(secs(60), UNIX_EPOCH + secs(7_260));
+
+        timer.update(Instant::now(), wall);
+        assert!(!try_recv(&rx));
+        assert_eq!(timer.deadline, Deadline::Aligned(UNIX_EPOCH + secs(3_660)));
+    }
+
+    #[test]
+    fn release_removes_timer() {
+        let rx = every(secs(3_600));
+        let id = channel_id(&rx);
+        let registered = || TIMERS.timers.lock().unwrap().iter().any(|t| t.id == id);
+
+        assert!(registered());
+        release(&rx);
+        assert!(!registered());
+    }
+}
@@ -0,0 +1,182 @@
+use image::{DynamicImage, FilterType, GenericImage, RgbaImage};
+use std::borrow::Cow;
+use std::cmp;
+
+/// Scaling of the bar's background image.
+///
+/// This is used by the [`BarBuilder`](struct.BarBuilder.html#method.background_mode) to select
+/// how the background image is fit to the size of the bar. It is applied again whenever the
+/// size of the bar changes.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BackgroundMode {
+    /// Draw the image in its original size at the top-left of the bar.
+    Unscaled,
+    /// Scale the image to the size of the bar, ignoring its aspect ratio.
+    Stretch,
+    /// Scale the image until it covers the whole bar, then crop the parts that overflow.
+    Fill,
+    /// Scale the image until it fits inside the bar and center it.
+    Fit,
+    /// Repeat the image in its original size, starting at the top-left.
+    Tile,
+    /// Center the image in its original size.
+    Center,
+}
+
+impl BackgroundMode {
+    // Scale the image and get the position it has to be drawn at
+    // Returns `None` if there is nothing to draw
+    pub(crate) fn apply<'a>(
+        &self,
+        image: &'a DynamicImage,
+        width: u16,
+        height: u16,
+    ) -> Option<(Cow<'a, DynamicImage>, (i16, i16))> {
+        let (w, h) = (u32::from(width), u32::from(height));
+        let (iw, ih) = (image.width(), image.height());
+        if w == 0 || h == 0 || iw == 0 || ih == 0 {
+            return None;
+        }
+
+        // Scale the image
+        let filter = FilterType::Triangle;
+        let image = match *self {
+            BackgroundMode::Stretch => Cow::Owned(image.resize_exact(w, h, filter)),
+            BackgroundMode::Fit => Cow::Owned(image.resize(w, h, filter)),
+            BackgroundMode::Fill => {
+                // Scale until both dimensions are at least as big as the bar
+                let (iw, ih) = (f64::from(iw), f64::from(ih));
+                let scale = f64::max(f64::from(w) / iw, f64::from(h) / ih);
+                let sw = cmp::max((iw * scale).ceil() as u32, w);
+                let sh = cmp::max((ih * scale).ceil() as u32, h);
+
+                // Crop the overflow on both sides
+                let mut scaled = image.resize_exact(sw, sh, filter);
+                Cow::Owned(scaled.crop((sw - w) / 2, (sh - h) / 2, w, h))
+            }
+            BackgroundMode::Tile => {
+                // Repeat the image once, so it can be drawn with a single request
+                let source = image.to_rgba();
+                let tiled = RgbaImage::from_fn(w, h, |x, y| *source.get_pixel(x % iw, y % ih));
+                Cow::Owned(DynamicImage::ImageRgba8(tiled))
+            }
+            BackgroundMode::Unscaled | BackgroundMode::Center => Cow::Borrowed(image),
+        };
+
+        // Get the position of the image
+        let position = match *self {
+            BackgroundMode::Fit | BackgroundMode::Center => {
+                let x = (i64::from(w) - i64::from(image.width())) / 2;
+                let y = (i64::from(h) - i64::from(image.height())) / 2;
+                (x as i16, y as i16)
+            }
+            _ => (0, 0),
+        };
+
+        Some((image, position))
+    }
+}
+
+impl Default for BackgroundMode {
+    fn default() -> Self {
+        BackgroundMode::Unscaled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    // Image with a different color in the top-left pixel
+    fn image(width: u32, height: u32) -> DynamicImage {
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            if x == 0 && y == 0 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        });
+        DynamicImage::ImageRgba8(image)
+    }
+
+    fn size(image: &DynamicImage) -> (u32, u32) {
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn unscaled_by_default() {
+        let source = image(40, 10);
+        let (image, position) = BackgroundMode::default().apply(&source, 100, 20).unwrap();
+        assert_eq!(size(&image), (40, 10));
+        assert_eq!(position, (0, 0));
+    }
+
+    #[test]
+    fn stretch_to_bar() {
+        let source = image(40, 10);
+        let (image, position) = BackgroundMode::Stretch.apply(&source, 100, 20).unwrap();
+        assert_eq!(size(&image), (100, 20));
+        assert_eq!(position, (0, 0));
+    }
+
+    #[test]
+    fn fill_crops_overflow() {
+        let source = image(40, 10);
+        let (image, position) = BackgroundMode::Fill.apply(&source, 100, 50).unwrap();
+        assert_eq!(size(&image), (100, 50));
+        assert_eq!(position, (0, 0));
+    }
+
+    #[test]
+    fn fit_is_centered() {
+        let source = image(40, 10);
+        let (image, position) = BackgroundMode::Fit.apply(&source, 100, 50).unwrap();
+        assert_eq!(size(&image), (100, 25));
+        assert_eq!(position, (0, 12));
+    }
+
+    #[test]
+    fn center_without_scaling() {
+        let source = image(40, 10);
+        let (image, position) = BackgroundMode::Center.apply(&source, 100, 20).unwrap();
+        assert_eq!(size(&image), (40, 10));
+        assert_eq!(position, (30, 5));
+
+        // Images bigger than the bar start outside of it
+        let (_, position) = BackgroundMode::Center.apply(&source, 20, 4).unwrap();
+        assert_eq!(position, (-10, -3));
+    }
+
+    #[test]
+    fn tile_into_single_image() {
+        let source = image(3, 2);
+        let (image, position) = BackgroundMode::Tile.apply(&source, 10, 5).unwrap();
+        assert_eq!(size(&image), (10, 5));
+        assert_eq!(position, (0, 0));
+
+        // The top-left pixel of every tile has the color of the original top-left pixel
+        let image = image.to_rgba();
+        for &(x, y) in &[(0, 0), (3, 0), (9, 4), (6, 2)] {
+            assert_eq!(*image.get_pixel(x, y), Rgba([255, 0, 0, 255]));
+        }
+        for &(x, y) in &[(1, 0), (0, 1), (5, 3), (8, 4)] {
+            assert_eq!(*image.get_pixel(x, y), Rgba([0, 0, 255, 255]));
+        }
+    }
+
+    #[test]
+    fn empty_image_is_skipped() {
+        let source = DynamicImage::new_rgba8(0, 0);
+        for mode in &[BackgroundMode::Fill, BackgroundMode::Fit, BackgroundMode::Tile] {
+            assert!(mode.apply(&source, 100, 20).is_none());
+        }
+    }
+
+    #[test]
+    fn empty_bar_is_skipped() {
+        let source = image(40, 10);
+        assert!(BackgroundMode::Fill.apply(&source, 0, 20).is_none());
+        assert!(BackgroundMode::Stretch.apply(&source, 100, 0).is_none());
+    }
+}
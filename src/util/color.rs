@@ -1,3 +1,5 @@
+use xcb;
+
 /// RGBA color structure.
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub struct Color {
@@ -29,6 +31,22 @@ impl Color {
         }
     }
 
+    // Convert to a premultiplied XRender color
+    pub(crate) fn as_render_color(&self) -> xcb::render::Color {
+        let alpha = u32::from(self.alpha);
+        let premultiply = |channel: u8| (u32::from(channel) * alpha * 257 / 255) as u16;
+        let (red, green) = (premultiply(self.red), premultiply(self.green));
+        xcb::render::Color::new(red, green, premultiply(self.blue), (alpha * 257) as u16)
+    }
+
+    // Convert to a pixel of a 32 bit window
+    // X.Org expects the color channels to be premultiplied with the alpha
+    pub(crate) fn as_premultiplied_pixel(&self) -> u32 {
+        let premultiply = |channel: u8| u32::from(channel) * u32::from(self.alpha) / 255;
+        ((u32::from(self.alpha)) << 24) + (premultiply(self.red) << 16)
+            + (premultiply(self.green) << 8) + premultiply(self.blue)
+    }
+
     // Change from 0..255 to 0..1
     pub(crate) fn as_fractions(&self) -> (f64, f64, f64, f64) {
         (
@@ -1,14 +1,22 @@
-// Geometry of the bar
+use util::position::Position;
+
+/// Position and size of a rectangle in pixels.
+///
+/// This is used for the geometry of components on the bar and the positions of events.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Geometry {
+    /// Horizontal position of the left edge.
     pub x: i16,
+    /// Vertical position of the top edge.
     pub y: i16,
+    /// Width of the rectangle.
     pub width: u16,
+    /// Height of the rectangle.
     pub height: u16,
 }
 
 impl Geometry {
-    // Helper for creating a geometry without struct syntax
+    /// Create a new geometry.
     pub fn new(x: i16, y: i16, width: u16, height: u16) -> Self {
         Geometry {
             x,
@@ -17,6 +25,48 @@ impl Geometry {
             height,
         }
     }
+
+    /// Check if the geometry doesn't cover any pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Check if two geometries share at least one pixel.
+    pub fn intersects(&self, other: &Geometry) -> bool {
+        let (x, y) = (i32::from(self.x), i32::from(self.y));
+        let (other_x, other_y) = (i32::from(other.x), i32::from(other.y));
+        !self.is_empty() && !other.is_empty() && x < other_x + i32::from(other.width)
+            && other_x < x + i32::from(self.width) && y < other_y + i32::from(other.height)
+            && other_y < y + i32::from(self.height)
+    }
+
+    /// Check if a point is inside of the geometry.
+    ///
+    /// The left and top edge are part of the geometry, the right and bottom edge are not.
+    pub fn contains(&self, x: i16, y: i16) -> bool {
+        let (x, y) = (i32::from(x), i32::from(y));
+        let (self_x, self_y) = (i32::from(self.x), i32::from(self.y));
+        x >= self_x && x < self_x + i32::from(self.width) && y >= self_y
+            && y < self_y + i32::from(self.height)
+    }
+
+    // Create a geometry along the axis components are stacked on
+    pub(crate) fn along(position: Position, offset: i16, length: u16, thickness: u16) -> Self {
+        if position.is_vertical() {
+            Geometry::new(0, offset, thickness, length)
+        } else {
+            Geometry::new(offset, 0, length, thickness)
+        }
+    }
+
+    // Size of the geometry along the axis components are stacked on
+    pub(crate) fn length(&self, position: Position) -> u16 {
+        if position.is_vertical() {
+            self.height
+        } else {
+            self.width
+        }
+    }
 }
 
 impl Default for Geometry {
@@ -29,3 +79,33 @@ impl Default for Geometry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_edges() {
+        let geometry = Geometry::new(10, 5, 20, 10);
+        assert!(geometry.contains(10, 5));
+        assert!(geometry.contains(29, 14));
+        assert!(!geometry.contains(9, 5));
+        assert!(!geometry.contains(10, 4));
+        assert!(!geometry.contains(30, 14));
+        assert!(!geometry.contains(29, 15));
+    }
+
+    #[test]
+    fn contains_nothing_when_empty() {
+        let geometry = Geometry::new(10, 0, 0, 30);
+        assert!(!geometry.contains(10, 0));
+        assert!(!Geometry::default().contains(0, 0));
+    }
+
+    #[test]
+    fn contains_without_overflow() {
+        let geometry = Geometry::new(i16::max_value() - 1, 0, u16::max_value(), 1);
+        assert!(geometry.contains(i16::max_value(), 0));
+        assert!(!geometry.contains(i16::min_value(), 0));
+    }
+}
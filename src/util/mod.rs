@@ -1,4 +1,6 @@
+pub mod background_mode;
 pub mod geometry;
+pub mod position;
 pub mod color;
 
 use std::sync::Arc;
@@ -10,5 +12,20 @@ pub fn screen(conn: &Arc<xcb::Connection>) -> Result<xcb::Screen> {
     conn.get_setup()
         .roots()
         .next()
-        .ok_or_else(|| ErrorKind::XcbNoScreenError(()).into())
+        .ok_or(Error::NoScreen)
+}
+
+// Get the first available TrueColor visualtype with 32 bit depth
+pub fn find_visualtype32<'s>(screen: &xcb::Screen<'s>) -> Option<xcb::Visualtype> {
+    for depth in screen.allowed_depths() {
+        if depth.depth() == 32 {
+            let visual = depth
+                .visuals()
+                .find(|v| v.class() == xcb::VISUAL_CLASS_TRUE_COLOR as u8);
+            if let Some(visual) = visual {
+                return Some(visual);
+            }
+        }
+    }
+    None
 }
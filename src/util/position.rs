@@ -0,0 +1,28 @@
+/// Screen edge the bar is docked to.
+///
+/// This is used by the [`BarBuilder`](struct.BarBuilder.html#method.position) to select where on
+/// the output the bar should be placed. Bars at the left or right edge are vertical, their
+/// components are stacked from top to bottom instead of from left to right.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Position {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Position {
+    // Check if components are stacked along the y axis
+    pub(crate) fn is_vertical(&self) -> bool {
+        match *self {
+            Position::Left | Position::Right => true,
+            Position::Top | Position::Bottom => false,
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::Top
+    }
+}